@@ -1,12 +1,53 @@
-use data_retrieve::load;
+use data_retrieve::{
+    best_known::BestKnownRegistry,
+    tour::{load_tour, write_tour},
+    tsplib::load_tsplib,
+};
 use graphics_engine::{App, EventsBridge};
-use std::{sync::mpsc::channel, thread::spawn};
+use std::{
+    env,
+    f64::consts::PI,
+    process,
+    sync::mpsc::{channel, TryRecvError},
+    thread::spawn,
+};
 use tsa_sim::{
-    cooling_methods::{CoolingMethod, ExpCooling},
+    calibration::Calibration,
+    christofides::christofides,
+    cooling_methods::{CoolingMethod, DynCooling, Schedule},
+    gap,
+    moves::Operator,
+    selection::OperatorSelection,
     TSAConfig, TSA,
 };
 
-fn handle_update<T: CoolingMethod + 'static>(tsa: &TSA<T>) -> Vec<graphics_engine::Object> {
+// Instances given only as a distance matrix have no coordinates, so their
+// cities are drawn evenly spaced on a circle instead.
+const LAYOUT_RADIUS: f64 = 500.0;
+
+fn layout_points<T: CoolingMethod + 'static>(tsa: &TSA<T>) -> Vec<(f64, f64)> {
+    if !tsa.data.is_empty() {
+        return tsa
+            .data
+            .iter()
+            .map(|item| (item.point.x, item.point.y))
+            .collect();
+    }
+    let size = tsa.distances.len();
+    (0..size)
+        .map(|index| {
+            let angle = 2.0 * PI * index as f64 / size as f64;
+            let x = LAYOUT_RADIUS * angle.cos();
+            let y = LAYOUT_RADIUS * angle.sin();
+            (x, y)
+        })
+        .collect()
+}
+
+fn handle_update<T: CoolingMethod + 'static>(
+    tsa: &TSA<T>,
+    points: &[(f64, f64)],
+) -> Vec<graphics_engine::Object> {
     let mut objects: Vec<graphics_engine::Object> = vec![];
     // tsa.gen_next_solution();
     // println!("{:?}", tsa.solution);
@@ -16,10 +57,8 @@ fn handle_update<T: CoolingMethod + 'static>(tsa: &TSA<T>) -> Vec<graphics_engin
         let origem_index = tsa.solution[solution_origem_index];
         let destiny_index = tsa.solution[solution_destiny_index];
 
-        let origem = &tsa.data[origem_index];
-        let destiny = &tsa.data[destiny_index];
-        let origem_point = (origem.point.x, origem.point.y);
-        let destiny_point = (destiny.point.x, destiny.point.y);
+        let origem_point = points[origem_index];
+        let destiny_point = points[destiny_index];
 
         objects.push(graphics_engine::Object::create(
             [origem_point, destiny_point].to_vec(),
@@ -28,66 +67,222 @@ fn handle_update<T: CoolingMethod + 'static>(tsa: &TSA<T>) -> Vec<graphics_engin
         ));
     }
 
-    for item in tsa.data.iter() {
+    for &(x, y) in points.iter() {
         objects.push(graphics_engine::Object::create_center(
-            item.point.x,
-            item.point.y,
+            x,
+            y,
             [1.0; 4],
             graphics_engine::ObjectType::CIRCLE,
         ));
     }
 
-    return objects;
+    objects
 }
 
 fn main() {
-    let data = load("data/inst_100.txt");
+    // app [instance] [--seed-tour <file.tour>] [--save-tour <file.tour>] [--seed <u64>]
+    //     [--cooling <schedule>] [--calibrate] [--christofides]
+    let mut fp = String::from("data/inst_100_raw.txt");
+    let mut seed_tour: Option<String> = None;
+    let mut save_tour: Option<String> = None;
+    let mut seed: Option<u64> = None;
+    let mut schedule = Schedule::Exp;
+    let mut calibrate = false;
+    let mut compare_christofides = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed-tour" => seed_tour = args.next(),
+            "--save-tour" => save_tour = args.next(),
+            "--seed" => {
+                seed = args.next().and_then(|seed| seed.parse().ok());
+                if seed.is_none() {
+                    eprintln!("--seed takes an unsigned integer");
+                    process::exit(1);
+                }
+            }
+            "--cooling" => {
+                let name = args.next().unwrap_or_default();
+                schedule = Schedule::from_name(&name).unwrap_or_else(|| {
+                    let names = Schedule::ALL.map(|schedule| schedule.to_string());
+                    eprintln!("--cooling takes one of {}", names.join(", "));
+                    process::exit(1);
+                });
+            }
+            "--calibrate" => calibrate = true,
+            "--christofides" => compare_christofides = true,
+            _ => fp = arg,
+        }
+    }
+    let instance = match load_tsplib(&fp) {
+        Ok(instance) => instance,
+        Err(err) => {
+            eprintln!("Could not load instance: {}", err);
+            process::exit(1);
+        }
+    };
 
     let initial_temperature = 800.0;
     let final_temperature = 20.0;
     let qtd_iters = 3000000;
     let qtd_iters_on_temp = 10;
-    let config = TSAConfig::<ExpCooling>::create(
+    let config = TSAConfig::<DynCooling>::create(
         final_temperature,
         initial_temperature,
         qtd_iters,
         qtd_iters_on_temp,
-    );
+    )
+    .with_schedule(schedule)
+    .with_operators(
+        Operator::ALL
+            .iter()
+            .map(|operator| (*operator, 1.0))
+            .collect(),
+    )
+    .with_operator_selection(OperatorSelection::Adaptive { reaction: 0.1 });
+    let config = match seed {
+        Some(seed) => config.with_seed(seed),
+        None => config,
+    };
+    let config = if calibrate {
+        config.with_calibration(Calibration::default())
+    } else {
+        config
+    };
 
     let (sender_signal, receiver_signal) = channel::<bool>();
-    let (sender_data, receiver_data) = channel::<Vec<graphics_engine::Object>>();
+    let (sender_data, receiver_data) = channel::<(Vec<graphics_engine::Object>, f64)>();
+
+    let name = instance.name.clone();
+    let mut tsa = match TSA::from_instance(instance, config) {
+        Ok(tsa) => tsa,
+        Err(err) => {
+            eprintln!("Could not load instance: {}", err.with_file(&fp));
+            process::exit(1);
+        }
+    };
+    if let Some(seed_tour) = seed_tour {
+        let seeded = load_tour(&seed_tour)
+            .map_err(|err| err.to_string())
+            .and_then(|tour| {
+                tsa.seed_from_tour(&tour)
+                    .ok_or_else(|| String::from("tour does not match the instance"))
+            });
+        if let Err(err) = seeded {
+            eprintln!("Could not seed from {}: {}", seed_tour, err);
+            process::exit(1);
+        }
+    }
 
-    let mut tsa = TSA::create(data, config);
+    let mut registry = BestKnownRegistry::load("best_solution_dist.txt").unwrap_or_else(|err| {
+        eprintln!("No best-known values: {}", err);
+        BestKnownRegistry::new()
+    });
+    if let Err(err) = registry.add_tour_beside(&fp) {
+        eprintln!("Could not load the optimal tour: {}", err);
+    }
+    let best_known = registry
+        .get(&name)
+        .and_then(|best_known| tsa.get_best_known_distance(best_known));
 
-    let max_x = tsa.data.iter().map(|item| item.point.x).max().unwrap();
-    let max_y = tsa.data.iter().map(|item| item.point.y).max().unwrap();
-    let min_x = tsa.data.iter().map(|item| item.point.x).min().unwrap();
-    let min_y = tsa.data.iter().map(|item| item.point.y).min().unwrap();
+    let points = layout_points(&tsa);
+    let max_x = points.iter().map(|point| point.0).fold(f64::MIN, f64::max);
+    let max_y = points.iter().map(|point| point.1).fold(f64::MIN, f64::max);
+    let min_x = points.iter().map(|point| point.0).fold(f64::MAX, f64::min);
+    let min_y = points.iter().map(|point| point.1).fold(f64::MAX, f64::min);
 
-    spawn(move || {
+    let tour_name = name.clone();
+    let worker = spawn(move || {
+        let mut stopped = false;
         loop {
-            tsa.gen_next_solution();
-            
-            let signal = receiver_signal.try_recv();
-            match signal {
-                Ok(msg) => {
-                    if msg {
-                        sender_data.send(handle_update(&tsa)).unwrap();
-                    } else {
-                        break;
-                    }
+            if !stopped {
+                if let Some(reason) = tsa.get_stop_reason() {
+                    println!("Stopped: {}", reason);
+                    stopped = true;
+                } else {
+                    tsa.gen_next_solution();
+                }
+            }
+
+            // Once stopped, wait for the window instead of spinning. A
+            // window that is gone counts as closed.
+            let signal = if stopped {
+                Some(receiver_signal.recv().unwrap_or(false))
+            } else {
+                match receiver_signal.try_recv() {
+                    Ok(msg) => Some(msg),
+                    Err(TryRecvError::Empty) => None,
+                    Err(TryRecvError::Disconnected) => Some(false),
+                }
+            };
+            if let Some(msg) = signal {
+                if msg {
+                    let objects = handle_update(&tsa, &points);
+                    sender_data
+                        .send((objects, tsa.get_current_distance()))
+                        .unwrap();
+                } else {
+                    break;
                 }
-                Err(_) => {}
             }
         }
         // println!("Sim iters {}", tsa.get_current_iter());
-        dbg!(tsa.get_current_iter());
-        dbg!(tsa.get_current_distance());
-        // dbg!(tsa.solution);
+        println!("seed: {}", tsa.get_seed());
+        let (initial_temperature, final_temperature) = tsa.get_temperature_range();
+        println!(
+            "temperatures: {} -> {}",
+            initial_temperature, final_temperature
+        );
+        println!("iterations: {}", tsa.get_current_iter());
+        println!("distance: {}", tsa.get_current_distance());
+        if let Some(best_known) = best_known {
+            println!("gap: {:.2}%", tsa.get_gap(best_known));
+        }
+        println!("tour: {:?}", tsa.get_solution_ids());
+        println!("best iteration: {}", tsa.get_best_iter());
+        println!("best distance: {}", tsa.get_best_distance());
+        if let Some(best_known) = best_known {
+            println!("best gap: {:.2}%", tsa.get_best_gap(best_known));
+        }
+        // Christofides' tour is the baseline the annealing is compared with.
+        // Its matching is cubic in the size, so it is only built on request.
+        if compare_christofides {
+            let christofides_distance = tsa.get_solution_distance(&christofides(&tsa.distances));
+            match best_known {
+                Some(best_known) => println!(
+                    "christofides: {} (gap {:.2}%)",
+                    christofides_distance,
+                    gap(christofides_distance, best_known)
+                ),
+                None => println!("christofides: {}", christofides_distance),
+            }
+        }
+        println!("operator     tries  accepted  improved  share");
+        for ((operator, stats), (_, share)) in tsa
+            .get_operator_stats()
+            .into_iter()
+            .zip(tsa.get_operator_shares())
+        {
+            println!(
+                "{:<10} {:>7} {:>8.2}% {:>8.2}% {:>5.2}",
+                operator.to_string(),
+                stats.tries,
+                100.0 * stats.acceptance_rate(),
+                100.0 * stats.improvement_rate(),
+                share
+            );
+        }
+        if let Some(save_tour) = save_tour {
+            let tour = tsa.get_best_tour(&format!("{}.tour", tour_name));
+            if let Err(err) = write_tour(&save_tour, &tour) {
+                eprintln!("Could not save tour to {}: {}", save_tour, err);
+            }
+        }
     });
 
-    let mut app = App::create("TSA", max_y + min_y, max_x + min_x);
+    let mut app = App::create("TSA", (min_x, min_y), (max_x, max_y));
 
+    let mut shown_distance = f64::NAN;
     let mut events = EventsBridge::create();
     while let Some(e) = events.next(&mut app.window_handle) {
         if let Some(args) = e.render_args() {
@@ -96,12 +291,29 @@ fn main() {
 
         if let Some(args) = e.update_args() {
             sender_signal.send(true).unwrap();
-            let objects = receiver_data.recv().unwrap();
+            let (objects, distance) = receiver_data.recv().unwrap();
             app.update(&args, objects);
+            if distance != shown_distance {
+                shown_distance = distance;
+                let title = match best_known {
+                    Some(best_known) => format!(
+                        "TSA {} - {} (gap {:.2}%)",
+                        name,
+                        distance,
+                        gap(distance, best_known)
+                    ),
+                    None => format!("TSA {} - {}", name, distance),
+                };
+                app.set_title(title);
+            }
         }
 
-        if let Some(_) = e.close_args() {
+        if e.close_args().is_some() {
             sender_signal.send(false).unwrap();
         }
     }
+    // The worker writes the final report and the tour once the window is
+    // closed, so wait for it before exiting.
+    drop(sender_signal);
+    worker.join().unwrap();
 }
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    error::{LoadError, LoadErrorKind},
+    tour::load_tour,
+};
+
+/// What is known about the optimum of an instance: its length, its tour
+/// (as node ids), or both.
+#[derive(Debug, Clone, Default)]
+pub struct BestKnown {
+    pub distance: Option<f64>,
+    pub tour: Option<Vec<usize>>,
+}
+
+/// Best-known solutions keyed by the instance NAME.
+#[derive(Debug, Clone, Default)]
+pub struct BestKnownRegistry {
+    entries: HashMap<String, BestKnown>,
+}
+
+impl BestKnownRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `name : distance` lines, as in `best_solution_dist.txt`.
+    pub fn load<P: AsRef<Path>>(fp: P) -> Result<Self, LoadError> {
+        let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+        Self::parse(&fd).map_err(|err| err.with_file(&fp))
+    }
+
+    pub fn parse(content: &str) -> Result<Self, LoadError> {
+        let mut registry = Self::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |kind| LoadError::at_line(index + 1, kind);
+            let (name, value) = line
+                .split_once(':')
+                .ok_or(at_line(LoadErrorKind::MissingColumn { column: "distance" }))?;
+            let distance = value.trim().parse::<f64>().map_err(|_| {
+                at_line(LoadErrorKind::NonNumeric {
+                    column: "distance",
+                    value: value.trim().to_string(),
+                })
+            })?;
+            registry.insert_distance(name.trim(), distance);
+        }
+        Ok(registry)
+    }
+
+    /// Adds an optimal tour from a `.opt.tour` file, keyed by its NAME
+    /// without the `.opt.tour` suffix.
+    pub fn add_tour<P: AsRef<Path>>(&mut self, fp: P) -> Result<(), LoadError> {
+        let tour = load_tour(fp)?;
+        let name = tour.name.trim_end_matches(".tour").trim_end_matches(".opt");
+        self.entries.entry(name.to_string()).or_default().tour = Some(tour.nodes);
+        Ok(())
+    }
+
+    /// Adds the `.opt.tour` file next to `instance_fp`, such as
+    /// `eil51.opt.tour` for `eil51.tsp`, when there is one. Returns whether
+    /// there was.
+    pub fn add_tour_beside<P: AsRef<Path>>(&mut self, instance_fp: P) -> Result<bool, LoadError> {
+        let fp = instance_fp.as_ref().with_extension("opt.tour");
+        if !fp.exists() {
+            return Ok(false);
+        }
+        self.add_tour(fp)?;
+        Ok(true)
+    }
+
+    pub fn insert_distance(&mut self, name: &str, distance: f64) {
+        self.entries.entry(name.to_string()).or_default().distance = Some(distance);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BestKnown> {
+        self.entries.get(name)
+    }
+
+    pub fn distance(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|best| best.distance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::best_known::BestKnownRegistry;
+
+    #[test]
+    fn reads_best_solution_dist() {
+        let registry = BestKnownRegistry::load("../best_solution_dist.txt").unwrap();
+        assert_eq!(registry.distance("eil51"), Some(426.0));
+        assert_eq!(registry.distance("kroA100"), Some(21282.0));
+        assert_eq!(registry.distance("pr1002"), None);
+    }
+
+    #[test]
+    fn malformed_line_is_an_error() {
+        let err = BestKnownRegistry::parse("eil51 : 426\nkroA100 21282\n").unwrap_err();
+        assert_eq!(err.line, Some(2));
+    }
+}
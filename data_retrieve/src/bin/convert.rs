@@ -0,0 +1,65 @@
+use data_retrieve::format::{load_instance, write_instance, Format};
+use std::{env, process};
+
+const USAGE: &str =
+    "usage: convert <input> <output> [--from tsplib|plain|csv|json] [--to tsplib|plain|csv|json] [--name name]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn parse_format(value: Option<String>) -> Format {
+    let value = value.unwrap_or_else(|| fail(USAGE));
+    value
+        .parse()
+        .unwrap_or_else(|_| fail(&format!("unknown format {:?}\n{}", value, USAGE)))
+}
+
+fn main() {
+    let mut paths = vec![];
+    let mut from: Option<Format> = None;
+    let mut to: Option<Format> = None;
+    let mut name: Option<String> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = Some(parse_format(args.next())),
+            "--to" => to = Some(parse_format(args.next())),
+            "--name" => name = args.next(),
+            _ => paths.push(arg),
+        }
+    }
+    let [input, output] = paths.as_slice() else {
+        fail(USAGE);
+    };
+
+    let mut instance = load_instance(input, from).unwrap_or_else(|err| fail(&err.to_string()));
+    if let Some(name) = name {
+        instance.name = name;
+    }
+    // Outputs without a known extension, such as `.txt`, default to TSPLIB.
+    let to = to
+        .or_else(|| Format::from_path(output))
+        .unwrap_or(Format::Tsplib);
+    if to != Format::Tsplib && instance.data.is_empty() {
+        fail(&format!(
+            "{} has no coordinates, it can only be written as tsplib",
+            input
+        ));
+    }
+    if to != Format::Tsplib && instance.edge_weights.is_some() {
+        eprintln!(
+            "warning: explicit edge weights are dropped, {} keeps only the nodes",
+            to
+        );
+    }
+
+    if let Err(err) = write_instance(output, &instance, to) {
+        fail(&format!("{}: {}", output, err));
+    }
+    println!(
+        "{} -> {} ({}, {} nodes)",
+        input, output, to, instance.dimension
+    );
+}
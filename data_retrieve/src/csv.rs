@@ -0,0 +1,153 @@
+use std::{fmt::Write, path::Path};
+
+use crate::{
+    error::{LoadError, LoadErrorKind},
+    parse_column, validate_ids, Data, DataNode, Point,
+};
+
+// Where each field lives in a row. Files without an `id` column number their
+// nodes from 1 in file order.
+#[derive(Debug, Clone, Copy)]
+struct Columns {
+    id: Option<usize>,
+    x: usize,
+    y: usize,
+}
+
+const DEFAULT_COLUMNS: Columns = Columns {
+    id: Some(0),
+    x: 1,
+    y: 2,
+};
+
+// A first row that is not all numbers is a header naming the columns.
+fn header_columns(items: &[&str]) -> Result<Option<Columns>, LoadErrorKind> {
+    if items.iter().all(|item| item.parse::<f64>().is_ok()) {
+        return Ok(None);
+    }
+    let find = |name: &str| {
+        items
+            .iter()
+            .position(|item| item.eq_ignore_ascii_case(name))
+    };
+    Ok(Some(Columns {
+        id: find("id"),
+        x: find("x").ok_or(LoadErrorKind::MissingColumn { column: "x" })?,
+        y: find("y").ok_or(LoadErrorKind::MissingColumn { column: "y" })?,
+    }))
+}
+
+fn parse_row(items: &[&str], columns: Columns, position: usize) -> Result<DataNode, LoadErrorKind> {
+    let group = match columns.id {
+        Some(id) => parse_column(items, id, "id")?,
+        None => position,
+    };
+    let x: f64 = parse_column(items, columns.x, "x")?;
+    let y: f64 = parse_column(items, columns.y, "y")?;
+    Ok(DataNode {
+        group,
+        point: Point { x, y },
+    })
+}
+
+pub fn load_csv<P: AsRef<Path>>(fp: P) -> Result<Data, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    parse_csv(&fd).map_err(|err| err.with_file(&fp))
+}
+
+/// Reads `id,x,y` rows. An optional header row may name the columns in any
+/// order, and may leave out `id`.
+pub fn parse_csv(content: &str) -> Result<Data, LoadError> {
+    let mut columns = None;
+    let mut data = vec![];
+    let mut lines = vec![];
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let items = line.split(',').map(str::trim).collect::<Vec<&str>>();
+        let row_columns = match columns {
+            Some(columns) => columns,
+            None => {
+                let header =
+                    header_columns(&items).map_err(|kind| LoadError::at_line(line_number, kind))?;
+                columns = Some(header.unwrap_or(DEFAULT_COLUMNS));
+                if header.is_some() {
+                    continue;
+                }
+                DEFAULT_COLUMNS
+            }
+        };
+        let node = parse_row(&items, row_columns, data.len() + 1)
+            .map_err(|kind| LoadError::at_line(line_number, kind))?;
+        data.push(node);
+        lines.push(line_number);
+    }
+    validate_ids(&data, &lines, None)?;
+
+    Ok(data)
+}
+
+/// Writes `data` as `id,x,y` rows under a header.
+pub fn format_csv(data: &Data) -> String {
+    let mut content = String::from("id,x,y\n");
+    for node in data.iter() {
+        writeln!(content, "{},{},{}", node.group, node.point.x, node.point.y).unwrap();
+    }
+    content
+}
+
+pub fn write_csv<P: AsRef<Path>>(fp: P, data: &Data) -> std::io::Result<()> {
+    std::fs::write(fp, format_csv(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        csv::{format_csv, parse_csv},
+        error::LoadErrorKind,
+    };
+
+    #[test]
+    fn reads_rows_with_and_without_header() {
+        let plain = parse_csv("1,0,0\n2, 1.5 ,-3\n").unwrap();
+        assert_eq!(plain.len(), 2);
+        assert_eq!(plain[1].point.x, 1.5);
+
+        let named = parse_csv("Y,X,ID\n0,1,7\n2,3,9\n").unwrap();
+        assert_eq!(named[1].group, 9);
+        assert_eq!(named[1].point.x, 3.0);
+        assert_eq!(named[1].point.y, 2.0);
+
+        let unnumbered = parse_csv("x,y\n5,6\n7,8\n").unwrap();
+        assert_eq!(unnumbered[1].group, 2);
+    }
+
+    #[test]
+    fn bad_rows_report_line() {
+        let err = parse_csv("id,x\n1,2\n").unwrap_err();
+        assert_eq!(err.line, Some(1));
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::MissingColumn { column: "y" }
+        ));
+
+        let err = parse_csv("id,x,y\n1,2,3\n\n2,a,3\n").unwrap_err();
+        assert_eq!(err.line, Some(4));
+
+        let err = parse_csv("1,2,3\n1,4,5\n").unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::DuplicateId { id: 1 }));
+    }
+
+    #[test]
+    fn written_csv_reads_back() {
+        let data = parse_csv("4,1.25,-2\n2,0,1e6\n").unwrap();
+        let content = format_csv(&data);
+        assert!(content.starts_with("id,x,y\n"));
+        let read = parse_csv(&content).unwrap();
+        assert_eq!(read[0].group, 4);
+        assert_eq!(read[1].point.y, 1e6);
+    }
+}
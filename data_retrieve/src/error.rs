@@ -0,0 +1,109 @@
+use std::{
+    fmt::{self, Display},
+    path::{Path, PathBuf},
+};
+
+use crate::tsplib::EdgeWeightType;
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    MissingColumn { column: &'static str },
+    NonNumeric { column: &'static str, value: String },
+    DuplicateId { id: usize },
+    IdOutOfRange { id: usize, max: Option<usize> },
+    InvalidValue { key: String, value: String },
+    DimensionMismatch { expected: usize, found: usize },
+    UnsupportedEdgeWeightType(EdgeWeightType),
+}
+
+/// Why an instance file could not be loaded, with the file and the 1-based
+/// line number where that is known.
+#[derive(Debug)]
+pub struct LoadError {
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub kind: LoadErrorKind,
+}
+
+impl LoadError {
+    pub fn new(kind: LoadErrorKind) -> Self {
+        Self {
+            file: None,
+            line: None,
+            kind,
+        }
+    }
+
+    pub fn at_line(line: usize, kind: LoadErrorKind) -> Self {
+        Self {
+            file: None,
+            line: Some(line),
+            kind,
+        }
+    }
+
+    pub fn with_file<P: AsRef<Path>>(mut self, fp: P) -> Self {
+        self.file = Some(fp.as_ref().to_path_buf());
+        self
+    }
+}
+
+impl Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadErrorKind::Io(err) => write!(f, "{}", err),
+            LoadErrorKind::Json(err) => write!(f, "{}", err),
+            LoadErrorKind::MissingColumn { column } => write!(f, "missing column `{}`", column),
+            LoadErrorKind::NonNumeric { column, value } => {
+                write!(f, "column `{}` is not a number: {:?}", column, value)
+            }
+            LoadErrorKind::DuplicateId { id } => write!(f, "duplicate id {}", id),
+            LoadErrorKind::IdOutOfRange { id, max: Some(max) } => {
+                write!(f, "id {} out of range 1..={}", id, max)
+            }
+            LoadErrorKind::IdOutOfRange { id, max: None } => {
+                write!(f, "id {} out of range, ids start at 1", id)
+            }
+            LoadErrorKind::InvalidValue { key, value } => {
+                write!(f, "invalid value for {}: {:?}", key, value)
+            }
+            LoadErrorKind::DimensionMismatch { expected, found } => {
+                write!(f, "expected {} entries, found {}", expected, found)
+            }
+            LoadErrorKind::UnsupportedEdgeWeightType(edge_weight_type) => {
+                write!(f, "EDGE_WEIGHT_TYPE {} is not supported", edge_weight_type)
+            }
+        }
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}", file.display())?,
+            None => write!(f, "<input>")?,
+        }
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Io(err) => Some(err),
+            LoadErrorKind::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        Self::new(LoadErrorKind::Io(err))
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::{self, Display},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    csv::{format_csv, parse_csv},
+    error::{LoadError, LoadErrorKind},
+    format_data,
+    json::{format_json, parse_json},
+    parse,
+    tsplib::{format_tsplib, parse_tsplib, Instance},
+};
+
+/// The instance file formats the toolchain reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Tsplib,
+    /// Whitespace separated `id x y` lines, as in `data/inst_100.txt`.
+    Plain,
+    Csv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = LoadErrorKind;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "tsplib" | "tsp" => Ok(Format::Tsplib),
+            "plain" | "txt" => Ok(Format::Plain),
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            _ => Err(LoadErrorKind::InvalidValue {
+                key: String::from("format"),
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Format::Tsplib => "tsplib",
+            Format::Plain => "plain",
+            Format::Csv => "csv",
+            Format::Json => "json",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Format {
+    /// The format implied by the file extension. `.txt` is left out on
+    /// purpose, both TSPLIB and plain files use it.
+    pub fn from_path<P: AsRef<Path>>(fp: P) -> Option<Self> {
+        let extension = fp.as_ref().extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "tsp" => Some(Format::Tsplib),
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the first non-empty line.
+    pub fn detect(content: &str) -> Self {
+        let first = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("");
+        let keyword = first.starts_with(|c: char| c.is_ascii_alphabetic());
+        if first.starts_with('[') || first.starts_with('{') {
+            Format::Json
+        } else if keyword && first.contains(':') {
+            Format::Tsplib
+        } else if first.contains(',') {
+            Format::Csv
+        } else if keyword {
+            Format::Tsplib
+        } else {
+            Format::Plain
+        }
+    }
+}
+
+/// Loads any supported format as an [`Instance`]. Without an explicit
+/// `format` it comes from the extension, or else from the content. Formats
+/// without a NAME are named after the file stem.
+pub fn load_instance<P: AsRef<Path>>(fp: P, format: Option<Format>) -> Result<Instance, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    let format = format
+        .or_else(|| Format::from_path(&fp))
+        .unwrap_or_else(|| Format::detect(&fd));
+    let mut instance = parse_instance(&fd, format).map_err(|err| err.with_file(&fp))?;
+    if instance.name.is_empty() {
+        if let Some(stem) = fp.as_ref().file_stem() {
+            instance.name = stem.to_string_lossy().to_string();
+        }
+    }
+    Ok(instance)
+}
+
+pub fn parse_instance(content: &str, format: Format) -> Result<Instance, LoadError> {
+    let data = match format {
+        Format::Tsplib => return parse_tsplib(content),
+        Format::Plain => parse(content)?,
+        Format::Csv => parse_csv(content)?,
+        Format::Json => parse_json(content)?,
+    };
+    Ok(Instance::from_data("", data))
+}
+
+/// Writes `instance` in `format`. Only TSPLIB keeps the specification and
+/// explicit weights, the other formats hold just the nodes.
+pub fn format_instance(instance: &Instance, format: Format) -> String {
+    match format {
+        Format::Tsplib => format_tsplib(instance),
+        Format::Plain => format_data(&instance.data),
+        Format::Csv => format_csv(&instance.data),
+        Format::Json => format_json(&instance.data),
+    }
+}
+
+pub fn write_instance<P: AsRef<Path>>(
+    fp: P,
+    instance: &Instance,
+    format: Format,
+) -> std::io::Result<()> {
+    std::fs::write(fp, format_instance(instance, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format::{format_instance, load_instance, parse_instance, Format};
+
+    #[test]
+    fn detects_format_from_path_and_content() {
+        assert_eq!(Format::from_path("a/b.CSV"), Some(Format::Csv));
+        assert_eq!(Format::from_path("../data/inst_51_raw.txt"), None);
+        assert_eq!(Format::detect("\n  [{\"id\": 1}]"), Format::Json);
+        assert_eq!(Format::detect("id,x,y\n"), Format::Csv);
+        assert_eq!(Format::detect("NAME : eil51\n"), Format::Tsplib);
+        assert_eq!(Format::detect("1 37 52\n"), Format::Plain);
+        assert_eq!("TSP".parse::<Format>().unwrap(), Format::Tsplib);
+        assert!("xml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn txt_instances_load_by_content() {
+        let raw = load_instance("../data/inst_51_raw.txt", None).unwrap();
+        assert_eq!(raw.name, "eil51");
+        let plain = load_instance("../data/inst_100.txt", None).unwrap();
+        assert_eq!(plain.name, "inst_100");
+        assert_eq!(plain.data.len(), 100);
+    }
+
+    #[test]
+    fn every_format_round_trips_the_nodes() {
+        let instance = load_instance("../data/inst_51_raw.txt", None).unwrap();
+        for format in [Format::Tsplib, Format::Plain, Format::Csv, Format::Json] {
+            let content = format_instance(&instance, format);
+            assert_eq!(Format::detect(&content), format);
+            let read = parse_instance(&content, format).unwrap();
+            assert_eq!(read.data.len(), 51);
+            assert_eq!(read.data[50].group, instance.data[50].group);
+            assert_eq!(read.data[50].point.x, instance.data[50].point.x);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{tsplib::Instance, Data, DataNode, Point};
+
+/// Families of random instances. The same generator and seed always produce
+/// the same nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    /// Points drawn uniformly in `[0, width] x [0, height]`.
+    Uniform {
+        size: usize,
+        width: f64,
+        height: f64,
+    },
+    /// Gaussian blobs of standard deviation `spread` around `clusters`
+    /// centers drawn uniformly in the box.
+    Clusters {
+        size: usize,
+        clusters: usize,
+        width: f64,
+        height: f64,
+        spread: f64,
+    },
+    /// A `rows x columns` lattice with each point moved by up to `jitter`
+    /// along both axes.
+    Grid {
+        rows: usize,
+        columns: usize,
+        spacing: f64,
+        jitter: f64,
+    },
+    /// Points evenly spaced on a circle, numbered in random order. The optimal
+    /// tour walks around the circle, see [`Generator::known_optimum`].
+    Circle { size: usize, radius: f64 },
+}
+
+// Standard normal sample through the Box-Muller transform.
+fn gaussian<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+fn nodes(points: Vec<(f64, f64)>) -> Data {
+    points
+        .into_iter()
+        .enumerate()
+        .map(|(index, (x, y))| DataNode {
+            group: index + 1,
+            point: Point { x, y },
+        })
+        .collect()
+}
+
+impl Generator {
+    pub fn name(&self) -> String {
+        match self {
+            Generator::Uniform { size, .. } => format!("uniform{}", size),
+            Generator::Clusters { size, clusters, .. } => {
+                format!("clusters{}x{}", clusters, size)
+            }
+            Generator::Grid { rows, columns, .. } => format!("grid{}x{}", rows, columns),
+            Generator::Circle { size, .. } => format!("circle{}", size),
+        }
+    }
+
+    pub fn generate(&self, seed: u64) -> Data {
+        let mut rng = StdRng::seed_from_u64(seed);
+        match *self {
+            Generator::Uniform {
+                size,
+                width,
+                height,
+            } => nodes(
+                (0..size)
+                    .map(|_| (rng.gen_range(0.0..=width), rng.gen_range(0.0..=height)))
+                    .collect(),
+            ),
+            Generator::Clusters {
+                size,
+                clusters,
+                width,
+                height,
+                spread,
+            } => {
+                let centers = (0..clusters.max(1))
+                    .map(|_| (rng.gen_range(0.0..=width), rng.gen_range(0.0..=height)))
+                    .collect::<Vec<_>>();
+                nodes(
+                    (0..size)
+                        .map(|index| {
+                            let (x, y) = centers[index % centers.len()];
+                            (
+                                x + spread * gaussian(&mut rng),
+                                y + spread * gaussian(&mut rng),
+                            )
+                        })
+                        .collect(),
+                )
+            }
+            Generator::Grid {
+                rows,
+                columns,
+                spacing,
+                jitter,
+            } => {
+                let mut points = vec![];
+                for row in 0..rows {
+                    for column in 0..columns {
+                        let x = column as f64 * spacing + rng.gen_range(-jitter..=jitter);
+                        let y = row as f64 * spacing + rng.gen_range(-jitter..=jitter);
+                        points.push((x, y));
+                    }
+                }
+                nodes(points)
+            }
+            Generator::Circle { size, radius } => {
+                let mut points = (0..size)
+                    .map(|index| {
+                        let angle = 2.0 * PI * index as f64 / size as f64;
+                        (radius * angle.cos(), radius * angle.sin())
+                    })
+                    .collect::<Vec<_>>();
+                points.shuffle(&mut rng);
+                nodes(points)
+            }
+        }
+    }
+
+    /// Optimal tour length under `EUC_2D`, where it is known analytically:
+    /// around the circle, `size` sides each rounded to the nearest integer
+    /// as TSPLIB rounds them. No two points are closer than a side, so no
+    /// tour is shorter.
+    pub fn known_optimum(&self) -> Option<f64> {
+        match *self {
+            Generator::Circle { size, radius } if size >= 3 => {
+                let side = 2.0 * radius * (PI / size as f64).sin();
+                Some(size as f64 * (side + 0.5).floor())
+            }
+            _ => None,
+        }
+    }
+
+    /// The generated nodes as an `EUC_2D` TSPLIB instance.
+    pub fn instance(&self, seed: u64) -> Instance {
+        let name = format!("{}_s{}", self.name(), seed);
+        let mut instance = Instance::from_data(&name, self.generate(seed));
+        instance.comment = format!("Generated {:?} with seed {}", self, seed);
+        instance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::Generator,
+        tsplib::{format_tsplib, parse_tsplib},
+    };
+
+    const GENERATORS: [Generator; 4] = [
+        Generator::Uniform {
+            size: 50,
+            width: 100.0,
+            height: 50.0,
+        },
+        Generator::Clusters {
+            size: 60,
+            clusters: 4,
+            width: 1000.0,
+            height: 1000.0,
+            spread: 20.0,
+        },
+        Generator::Grid {
+            rows: 5,
+            columns: 8,
+            spacing: 10.0,
+            jitter: 1.0,
+        },
+        Generator::Circle {
+            size: 30,
+            radius: 100.0,
+        },
+    ];
+
+    #[test]
+    fn same_seed_same_instance() {
+        for generator in GENERATORS {
+            let a = generator.generate(7);
+            let b = generator.generate(7);
+            let c = generator.generate(8);
+            assert_eq!(a.len(), b.len());
+            assert!(a.iter().zip(b.iter()).all(|(a, b)| a.point.x == b.point.x
+                && a.point.y == b.point.y
+                && a.group == b.group));
+            assert!(a.iter().zip(c.iter()).any(|(a, c)| a.point.x != c.point.x));
+        }
+    }
+
+    #[test]
+    fn generators_respect_their_shape() {
+        let uniform = GENERATORS[0].generate(1);
+        assert_eq!(uniform.len(), 50);
+        assert!(uniform
+            .iter()
+            .all(|node| (0.0..=100.0).contains(&node.point.x)
+                && (0.0..=50.0).contains(&node.point.y)));
+
+        let grid = GENERATORS[2].generate(1);
+        assert_eq!(grid.len(), 40);
+        assert!(grid
+            .iter()
+            .all(|node| node.point.x >= -1.0 && node.point.x <= 71.0));
+
+        let circle = GENERATORS[3].generate(1);
+        assert!(circle.iter().all(|node| {
+            let radius = (node.point.x.powi(2) + node.point.y.powi(2)).sqrt();
+            (radius - 100.0).abs() < 1e-9
+        }));
+        // 30 sides of 20.9 each, rounded to 21.
+        assert_eq!(GENERATORS[3].known_optimum(), Some(630.0));
+    }
+
+    #[test]
+    fn generated_instance_round_trips_through_tsplib() {
+        let instance = GENERATORS[1].instance(42);
+        assert_eq!(instance.name, "clusters4x60_s42");
+        let read = parse_tsplib(&format_tsplib(&instance)).unwrap();
+        assert_eq!(read.dimension, 60);
+        assert_eq!(read.data[59].point.y, instance.data[59].point.y);
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check_ids,
+    error::{LoadError, LoadErrorKind},
+    Data, DataNode, Point,
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonNode {
+    id: usize,
+    x: f64,
+    y: f64,
+}
+
+// Exports come either as a bare list of nodes or wrapped in an object with
+// a `nodes` field next to other metadata.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Nodes(Vec<JsonNode>),
+    Wrapped { nodes: Vec<JsonNode> },
+}
+
+pub fn load_json<P: AsRef<Path>>(fp: P) -> Result<Data, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    parse_json(&fd).map_err(|err| err.with_file(&fp))
+}
+
+/// Reads `[{"id": 1, "x": 0.0, "y": 0.0}, ...]`, or the same list under a
+/// `nodes` key.
+pub fn parse_json(content: &str) -> Result<Data, LoadError> {
+    // Going through a `Value` keeps the line of syntax errors, which the
+    // untagged shape match would otherwise lose.
+    let nodes = match serde_json::from_str(content).and_then(serde_json::from_value) {
+        Ok(JsonData::Nodes(nodes)) | Ok(JsonData::Wrapped { nodes }) => nodes,
+        Err(err) => {
+            return Err(LoadError {
+                file: None,
+                line: Some(err.line()).filter(|line| *line > 0),
+                kind: LoadErrorKind::Json(err),
+            });
+        }
+    };
+    let data = nodes
+        .into_iter()
+        .map(|node| DataNode {
+            group: node.id,
+            point: Point {
+                x: node.x,
+                y: node.y,
+            },
+        })
+        .collect();
+    check_ids(&data, None).map_err(|(_, kind)| LoadError::new(kind))?;
+
+    Ok(data)
+}
+
+/// Writes `data` as a pretty-printed list of `{"id", "x", "y"}` objects.
+pub fn format_json(data: &Data) -> String {
+    let nodes = data
+        .iter()
+        .map(|node| JsonNode {
+            id: node.group,
+            x: node.point.x,
+            y: node.point.y,
+        })
+        .collect::<Vec<_>>();
+    let mut content = serde_json::to_string_pretty(&nodes).unwrap();
+    content.push('\n');
+    content
+}
+
+pub fn write_json<P: AsRef<Path>>(fp: P, data: &Data) -> std::io::Result<()> {
+    std::fs::write(fp, format_json(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::LoadErrorKind,
+        json::{format_json, parse_json},
+    };
+
+    #[test]
+    fn reads_bare_and_wrapped_lists() {
+        let bare =
+            parse_json(r#"[{"id": 3, "x": 1.5, "y": -2}, {"id": 1, "x": 0, "y": 0}]"#).unwrap();
+        assert_eq!(bare[0].group, 3);
+        assert_eq!(bare[0].point.y, -2.0);
+
+        let wrapped =
+            parse_json(r#"{"name": "tiny", "nodes": [{"id": 1, "x": 4, "y": 5}]}"#).unwrap();
+        assert_eq!(wrapped[0].point.x, 4.0);
+    }
+
+    #[test]
+    fn malformed_json_is_an_error() {
+        let err = parse_json("[\n{\"id\": 1, \"x\": 0,}\n]").unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(matches!(err.kind, LoadErrorKind::Json(_)));
+
+        let err = parse_json(r#"[{"id": 1, "x": 0}]"#).unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::Json(_)));
+
+        let err = parse_json(r#"[{"id": 0, "x": 0, "y": 0}]"#).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::IdOutOfRange { id: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn written_json_reads_back() {
+        let data = parse_json(r#"[{"id": 7, "x": 0.25, "y": 1e6}]"#).unwrap();
+        let read = parse_json(&format_json(&data)).unwrap();
+        assert_eq!(read[0].group, 7);
+        assert_eq!(read[0].point.x, 0.25);
+        assert_eq!(read[0].point.y, 1e6);
+    }
+}
@@ -1,52 +1,241 @@
+pub mod best_known;
+pub mod csv;
+pub mod error;
+pub mod format;
+pub mod generator;
+pub mod json;
+pub mod tour;
+pub mod tsplib;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use error::{LoadError, LoadErrorKind};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Point {
-    pub x: usize,
-    pub y: usize,
+    pub x: f64,
+    pub y: f64,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct DataNode {
-    pub group: u8,
+    pub group: usize,
     pub point: Point,
 }
 
 pub type Data = Vec<DataNode>;
 
-pub fn load(fp: &'static str) -> Data {
+pub fn load<P: AsRef<Path>>(fp: P) -> Result<Data, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    parse(&fd).map_err(|err| err.with_file(&fp))
+}
+
+pub fn parse(content: &str) -> Result<Data, LoadError> {
     let mut data = vec![];
-    let fd = std::fs::read_to_string(fp).expect("To be able to open the file");
-    let lines = fd.lines();
-    for line in lines {
-        let items = line.split_whitespace().collect::<Vec<&str>>();
-        // println!("{}", line.to_string());
-        let group: u8 = items[0].parse().unwrap();
-        let x: usize = items[1].parse().unwrap();
-        let y: usize = items[2].parse().unwrap();
-        // println!("|{}\t||{}\t||{}|", x, y, group);
-        data.push(DataNode {
-            group,
-            point: Point { x, y },
-        });
+    let mut lines = vec![];
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let node = parse_node(line).map_err(|kind| LoadError::at_line(index + 1, kind))?;
+        data.push(node);
+        lines.push(index + 1);
+    }
+    validate_ids(&data, &lines, None)?;
+
+    Ok(data)
+}
+
+/// Writes `data` in the plain `id x y` format read by [`parse`].
+pub fn format_data(data: &Data) -> String {
+    let mut content = String::new();
+    for node in data.iter() {
+        writeln!(content, "{} {} {}", node.group, node.point.x, node.point.y).unwrap();
+    }
+    content
+}
+
+pub fn write_data<P: AsRef<Path>>(fp: P, data: &Data) -> std::io::Result<()> {
+    std::fs::write(fp, format_data(data))
+}
+
+fn parse_column<T: std::str::FromStr>(
+    items: &[&str],
+    index: usize,
+    column: &'static str,
+) -> Result<T, LoadErrorKind> {
+    let item = items
+        .get(index)
+        .ok_or(LoadErrorKind::MissingColumn { column })?;
+    item.parse().map_err(|_| LoadErrorKind::NonNumeric {
+        column,
+        value: item.to_string(),
+    })
+}
+
+fn parse_node(line: &str) -> Result<DataNode, LoadErrorKind> {
+    let items = line.split_whitespace().collect::<Vec<&str>>();
+    let group: usize = parse_column(&items, 0, "id")?;
+    let x: f64 = parse_column(&items, 1, "x")?;
+    let y: f64 = parse_column(&items, 2, "y")?;
+    Ok(DataNode {
+        group,
+        point: Point { x, y },
+    })
+}
+
+// Ids must be unique and positive; `max` bounds them when they address rows
+// of an explicit matrix. Errors carry the position of the offending node.
+fn check_ids(data: &Data, max: Option<usize>) -> Result<(), (usize, LoadErrorKind)> {
+    let mut seen = HashSet::new();
+    for (position, node) in data.iter().enumerate() {
+        let id = node.group;
+        if id == 0 || max.is_some_and(|max| id > max) {
+            return Err((position, LoadErrorKind::IdOutOfRange { id, max }));
+        }
+        if !seen.insert(id) {
+            return Err((position, LoadErrorKind::DuplicateId { id }));
+        }
+    }
+    Ok(())
+}
+
+fn validate_ids(data: &Data, lines: &[usize], max: Option<usize>) -> Result<(), LoadError> {
+    check_ids(data, max).map_err(|(position, kind)| LoadError::at_line(lines[position], kind))
+}
+
+/// Maps the ids used in the instance file to the dense `0..n` indices used by
+/// the distance matrix and the solution, and back.
+#[derive(Debug, Clone, Default)]
+pub struct NodeIndex {
+    ids: Vec<usize>,
+    indices: HashMap<usize, usize>,
+}
+
+impl NodeIndex {
+    pub fn from_data(data: &Data) -> Self {
+        Self::from_ids(data.iter().map(|node| node.group).collect())
+    }
+
+    /// Ids `1..=len`, for instances given only as a matrix.
+    pub fn sequential(len: usize) -> Self {
+        Self::from_ids((1..=len).collect())
+    }
+
+    pub fn from_ids(ids: Vec<usize>) -> Self {
+        let indices = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        Self { ids, indices }
     }
 
-    return data;
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn id(&self, index: usize) -> usize {
+        self.ids[index]
+    }
+
+    pub fn index(&self, id: usize) -> Option<usize> {
+        self.indices.get(&id).copied()
+    }
+
+    pub fn to_ids(&self, solution: &[usize]) -> Vec<usize> {
+        solution.iter().map(|index| self.id(*index)).collect()
+    }
+
+    pub fn to_indices(&self, ids: &[usize]) -> Option<Vec<usize>> {
+        ids.iter().map(|id| self.index(*id)).collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::load;
+    use crate::{error::LoadErrorKind, format_data, load, parse, NodeIndex};
 
     #[test]
     fn path_100_has_100_items() {
-        let data = load("../data/inst_100.txt");
+        let data = load("../data/inst_100.txt").unwrap();
         assert_eq!(data.len(), 100);
     }
 
     #[test]
     fn path_100_item_54_x_is_2945_y_is_1622() {
-        let data = load("../data/inst_100.txt");
+        let data = load("../data/inst_100.txt").unwrap();
         assert_eq!(data[54].group, 55);
-        assert_eq!(data[54].point.x, 2945);
-        assert_eq!(data[54].point.y, 1622);
+        assert_eq!(data[54].point.x, 2945.0);
+        assert_eq!(data[54].point.y, 1622.0);
+    }
+
+    #[test]
+    fn real_and_negative_coordinates() {
+        let data = parse("1 1.2345e+03 -7.5\n2 -0.25 42\n").unwrap();
+        assert_eq!(data[0].point.x, 1234.5);
+        assert_eq!(data[0].point.y, -7.5);
+        assert_eq!(data[1].point.x, -0.25);
+    }
+
+    #[test]
+    fn missing_file_reports_path() {
+        let err = load(String::from("../data/missing.txt")).unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::Io(_)));
+        assert!(err.to_string().starts_with("../data/missing.txt"));
+    }
+
+    #[test]
+    fn bad_lines_report_line_and_reason() {
+        let err = parse("1 1 1\n2 3\n").unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::MissingColumn { column: "y" }
+        ));
+
+        let err = parse("1 1 1\n2 a 3\n").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::NonNumeric { column: "x", .. }
+        ));
+
+        let err = parse("1 1 1\n1 2 3\n").unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::DuplicateId { id: 1 }));
+
+        let err = parse("1 1 1\n0 2 3\n").unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::IdOutOfRange { id: 0, max: None }
+        ));
+    }
+
+    #[test]
+    fn sparse_ids_map_to_dense_indices() {
+        let data = parse("300 0 0\n7 1 1\n1000 2 2\n").unwrap();
+        let index = NodeIndex::from_data(&data);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.index(1000), Some(2));
+        assert_eq!(index.index(1), None);
+        assert_eq!(index.to_ids(&[1, 2, 0]), vec![7, 1000, 300]);
+        assert_eq!(index.to_indices(&[300, 7]), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn written_data_reads_back() {
+        let data = parse("3 1.5 -2\n1 0 1e6\n").unwrap();
+        let read = parse(&format_data(&data)).unwrap();
+        assert_eq!(read[0].group, 3);
+        assert_eq!(read[0].point.y, -2.0);
+        assert_eq!(read[1].point.y, 1e6);
     }
 }
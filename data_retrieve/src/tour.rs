@@ -0,0 +1,173 @@
+use std::{collections::HashSet, fmt::Write, path::Path};
+
+use crate::error::{LoadError, LoadErrorKind};
+
+/// A TSPLIB `.tour` file: the node ids of the tour in visiting order.
+#[derive(Debug, Clone, Default)]
+pub struct Tour {
+    pub name: String,
+    pub comment: String,
+    pub dimension: usize,
+    pub nodes: Vec<usize>,
+}
+
+impl Tour {
+    pub fn create(name: &str, nodes: Vec<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            comment: String::new(),
+            dimension: nodes.len(),
+            nodes,
+        }
+    }
+}
+
+pub fn load_tour<P: AsRef<Path>>(fp: P) -> Result<Tour, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    parse_tour(&fd).map_err(|err| err.with_file(&fp))
+}
+
+pub fn parse_tour(content: &str) -> Result<Tour, LoadError> {
+    let mut tour = Tour::default();
+    let mut seen = HashSet::new();
+    let mut in_section = false;
+    let mut last_line = 0;
+    'lines: for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        last_line = line_number;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if !in_section {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (line, ""),
+            };
+            match key {
+                "NAME" => tour.name = value.to_string(),
+                "COMMENT" => {
+                    if !tour.comment.is_empty() {
+                        tour.comment.push('\n');
+                    }
+                    tour.comment.push_str(value);
+                }
+                "DIMENSION" => {
+                    tour.dimension = value.parse().map_err(|_| {
+                        LoadError::at_line(
+                            line_number,
+                            LoadErrorKind::InvalidValue {
+                                key: key.to_string(),
+                                value: value.to_string(),
+                            },
+                        )
+                    })?
+                }
+                "TOUR_SECTION" => in_section = true,
+                "EOF" => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        // The section may hold several ids per line and ends with -1.
+        for item in line.split_whitespace() {
+            if item == "-1" || item == "EOF" {
+                break 'lines;
+            }
+            let id = item.parse::<usize>().map_err(|_| {
+                LoadError::at_line(
+                    line_number,
+                    LoadErrorKind::NonNumeric {
+                        column: "id",
+                        value: item.to_string(),
+                    },
+                )
+            })?;
+            if !seen.insert(id) {
+                return Err(LoadError::at_line(
+                    line_number,
+                    LoadErrorKind::DuplicateId { id },
+                ));
+            }
+            tour.nodes.push(id);
+        }
+    }
+
+    if tour.dimension == 0 {
+        tour.dimension = tour.nodes.len();
+    }
+    if tour.nodes.len() != tour.dimension {
+        return Err(LoadError::at_line(
+            last_line,
+            LoadErrorKind::DimensionMismatch {
+                expected: tour.dimension,
+                found: tour.nodes.len(),
+            },
+        ));
+    }
+
+    Ok(tour)
+}
+
+pub fn format_tour(tour: &Tour) -> String {
+    let mut content = String::new();
+    writeln!(content, "NAME : {}", tour.name).unwrap();
+    writeln!(content, "TYPE : TOUR").unwrap();
+    for comment in tour.comment.lines() {
+        writeln!(content, "COMMENT : {}", comment).unwrap();
+    }
+    writeln!(content, "DIMENSION : {}", tour.nodes.len()).unwrap();
+    writeln!(content, "TOUR_SECTION").unwrap();
+    for id in tour.nodes.iter() {
+        writeln!(content, "{}", id).unwrap();
+    }
+    writeln!(content, "-1").unwrap();
+    writeln!(content, "EOF").unwrap();
+    content
+}
+
+pub fn write_tour<P: AsRef<Path>>(fp: P, tour: &Tour) -> std::io::Result<()> {
+    std::fs::write(fp, format_tour(tour))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tour::{format_tour, parse_tour, Tour};
+
+    #[test]
+    fn reads_tour_section_until_terminator() {
+        let content =
+            "NAME : tiny.opt.tour\nTYPE : TOUR\nDIMENSION : 4\nTOUR_SECTION\n1\n3 2\n4\n-1\nEOF\n";
+        let tour = parse_tour(content).unwrap();
+        assert_eq!(tour.name, "tiny.opt.tour");
+        assert_eq!(tour.nodes, vec![1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn short_tour_is_an_error() {
+        let content = "DIMENSION : 4\nTOUR_SECTION\n1\n2\n-1\n";
+        assert!(parse_tour(content).is_err());
+    }
+
+    #[test]
+    fn duplicate_node_is_an_error() {
+        let content = "DIMENSION : 3\nTOUR_SECTION\n1\n2\n2\n-1\n";
+        let err = parse_tour(content).unwrap_err();
+        assert_eq!(err.line, Some(5));
+    }
+
+    #[test]
+    fn written_tour_reads_back() {
+        let mut tour = Tour::create("eil51.tour", vec![7, 1000, 3]);
+        tour.comment = String::from("Length 12");
+        let content = format_tour(&tour);
+        assert!(content.ends_with("3\n-1\nEOF\n"));
+        let read = parse_tour(&content).unwrap();
+        assert_eq!(read.name, "eil51.tour");
+        assert_eq!(read.comment, "Length 12");
+        assert_eq!(read.dimension, 3);
+        assert_eq!(read.nodes, tour.nodes);
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    fmt::{self, Display, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    error::{LoadError, LoadErrorKind},
+    parse_node, validate_ids, Data,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeWeightType {
+    Explicit,
+    Euc2D,
+    Euc3D,
+    Max2D,
+    Max3D,
+    Man2D,
+    Man3D,
+    Ceil2D,
+    Geo,
+    Att,
+    Xray1,
+    Xray2,
+    Special,
+}
+
+impl FromStr for EdgeWeightType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EXPLICIT" => Ok(Self::Explicit),
+            "EUC_2D" => Ok(Self::Euc2D),
+            "EUC_3D" => Ok(Self::Euc3D),
+            "MAX_2D" => Ok(Self::Max2D),
+            "MAX_3D" => Ok(Self::Max3D),
+            "MAN_2D" => Ok(Self::Man2D),
+            "MAN_3D" => Ok(Self::Man3D),
+            "CEIL_2D" => Ok(Self::Ceil2D),
+            "GEO" => Ok(Self::Geo),
+            "ATT" => Ok(Self::Att),
+            "XRAY1" => Ok(Self::Xray1),
+            "XRAY2" => Ok(Self::Xray2),
+            "SPECIAL" => Ok(Self::Special),
+            _ => Err(format!("unknown EDGE_WEIGHT_TYPE {}", s)),
+        }
+    }
+}
+
+impl Display for EdgeWeightType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Explicit => "EXPLICIT",
+            Self::Euc2D => "EUC_2D",
+            Self::Euc3D => "EUC_3D",
+            Self::Max2D => "MAX_2D",
+            Self::Max3D => "MAX_3D",
+            Self::Man2D => "MAN_2D",
+            Self::Man3D => "MAN_3D",
+            Self::Ceil2D => "CEIL_2D",
+            Self::Geo => "GEO",
+            Self::Att => "ATT",
+            Self::Xray1 => "XRAY1",
+            Self::Xray2 => "XRAY2",
+            Self::Special => "SPECIAL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeWeightFormat {
+    FullMatrix,
+    UpperRow,
+    LowerRow,
+    UpperDiagRow,
+    LowerDiagRow,
+    UpperCol,
+    LowerCol,
+    UpperDiagCol,
+    LowerDiagCol,
+}
+
+impl FromStr for EdgeWeightFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FULL_MATRIX" => Ok(Self::FullMatrix),
+            "UPPER_ROW" => Ok(Self::UpperRow),
+            "LOWER_ROW" => Ok(Self::LowerRow),
+            "UPPER_DIAG_ROW" => Ok(Self::UpperDiagRow),
+            "LOWER_DIAG_ROW" => Ok(Self::LowerDiagRow),
+            "UPPER_COL" => Ok(Self::UpperCol),
+            "LOWER_COL" => Ok(Self::LowerCol),
+            "UPPER_DIAG_COL" => Ok(Self::UpperDiagCol),
+            "LOWER_DIAG_COL" => Ok(Self::LowerDiagCol),
+            _ => Err(format!("unknown EDGE_WEIGHT_FORMAT {}", s)),
+        }
+    }
+}
+
+impl Display for EdgeWeightFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::FullMatrix => "FULL_MATRIX",
+            Self::UpperRow => "UPPER_ROW",
+            Self::LowerRow => "LOWER_ROW",
+            Self::UpperDiagRow => "UPPER_DIAG_ROW",
+            Self::LowerDiagRow => "LOWER_DIAG_ROW",
+            Self::UpperCol => "UPPER_COL",
+            Self::LowerCol => "LOWER_COL",
+            Self::UpperDiagCol => "UPPER_DIAG_COL",
+            Self::LowerDiagCol => "LOWER_DIAG_COL",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl EdgeWeightFormat {
+    /// Number of weights the section holds for a problem of `dimension` nodes.
+    pub fn weight_count(&self, dimension: usize) -> usize {
+        match self {
+            Self::FullMatrix => dimension * dimension,
+            Self::UpperRow | Self::LowerRow | Self::UpperCol | Self::LowerCol => {
+                dimension * (dimension.saturating_sub(1)) / 2
+            }
+            _ => dimension * (dimension + 1) / 2,
+        }
+    }
+
+    /// Lays the flat `EDGE_WEIGHT_SECTION` out as a `dimension x dimension` matrix.
+    pub fn matrix(&self, dimension: usize, weights: &[f64]) -> Vec<Vec<f64>> {
+        let mut matrix = vec![vec![0.0; dimension]; dimension];
+        let mut weights = weights.iter();
+        if let Self::FullMatrix = self {
+            for row in matrix.iter_mut() {
+                for cell in row.iter_mut() {
+                    *cell = *weights.next().unwrap();
+                }
+            }
+            return matrix;
+        }
+
+        // A column-wise upper triangle lists the same values, in the same
+        // order, as a row-wise lower triangle (and vice versa).
+        let (upper, diagonal) = match self {
+            Self::UpperRow | Self::LowerCol => (true, false),
+            Self::LowerRow | Self::UpperCol => (false, false),
+            Self::UpperDiagRow | Self::LowerDiagCol => (true, true),
+            _ => (false, true),
+        };
+        let mut cells = vec![];
+        for i in 0..dimension {
+            let columns = match (upper, diagonal) {
+                (true, false) => i + 1..dimension,
+                (true, true) => i..dimension,
+                (false, false) => 0..i,
+                (false, true) => 0..i + 1,
+            };
+            cells.extend(columns.map(|j| (i, j)));
+        }
+        for ((i, j), weight) in cells.into_iter().zip(weights) {
+            matrix[i][j] = *weight;
+            matrix[j][i] = *weight;
+        }
+        matrix
+    }
+}
+
+/// A TSPLIB instance: the specification part plus the nodes read from
+/// `NODE_COORD_SECTION` (or `DISPLAY_DATA_SECTION` for explicit instances)
+/// and, when `EDGE_WEIGHT_TYPE` is `EXPLICIT`, the distance matrix.
+#[derive(Debug, Clone, Default)]
+pub struct Instance {
+    pub name: String,
+    pub problem_type: String,
+    pub comment: String,
+    pub dimension: usize,
+    pub edge_weight_type: Option<EdgeWeightType>,
+    pub edge_weight_format: Option<EdgeWeightFormat>,
+    pub node_coord_type: Option<String>,
+    pub display_data_type: Option<String>,
+    pub data: Data,
+    pub edge_weights: Option<Vec<Vec<f64>>>,
+}
+
+impl Instance {
+    /// A `EUC_2D` instance over `data`.
+    pub fn from_data(name: &str, data: Data) -> Self {
+        Self {
+            name: name.to_string(),
+            problem_type: String::from("TSP"),
+            dimension: data.len(),
+            edge_weight_type: Some(EdgeWeightType::Euc2D),
+            data,
+            ..Self::default()
+        }
+    }
+}
+
+enum Section {
+    None,
+    NodeCoord,
+    DisplayData,
+    EdgeWeight,
+    Unsupported,
+}
+
+pub fn load_tsplib<P: AsRef<Path>>(fp: P) -> Result<Instance, LoadError> {
+    let fd = std::fs::read_to_string(&fp).map_err(|err| LoadError::from(err).with_file(&fp))?;
+    parse_tsplib(&fd).map_err(|err| err.with_file(&fp))
+}
+
+fn parse_value<T: FromStr>(key: &str, value: &str) -> Result<T, LoadErrorKind> {
+    value.parse().map_err(|_| LoadErrorKind::InvalidValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    })
+}
+
+pub fn parse_tsplib(content: &str) -> Result<Instance, LoadError> {
+    let mut instance = Instance::default();
+    let mut section = Section::None;
+    let mut node_lines = vec![];
+    let mut display_data: Data = vec![];
+    let mut display_lines = vec![];
+    let mut weights: Vec<f64> = vec![];
+    let mut last_line = 0;
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let at_line = |kind| LoadError::at_line(line_number, kind);
+        last_line = line_number;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Keywords and section headers start with a letter, data lines with a number.
+        if line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (line, ""),
+            };
+            section = Section::None;
+            match key {
+                "NAME" => instance.name = value.to_string(),
+                "TYPE" => instance.problem_type = value.to_string(),
+                "COMMENT" => {
+                    if !instance.comment.is_empty() {
+                        instance.comment.push('\n');
+                    }
+                    instance.comment.push_str(value);
+                }
+                "DIMENSION" => instance.dimension = parse_value(key, value).map_err(at_line)?,
+                "EDGE_WEIGHT_TYPE" => {
+                    instance.edge_weight_type = Some(parse_value(key, value).map_err(at_line)?)
+                }
+                "EDGE_WEIGHT_FORMAT" => {
+                    instance.edge_weight_format = Some(parse_value(key, value).map_err(at_line)?)
+                }
+                "NODE_COORD_TYPE" => instance.node_coord_type = Some(value.to_string()),
+                "DISPLAY_DATA_TYPE" => instance.display_data_type = Some(value.to_string()),
+                "NODE_COORD_SECTION" => section = Section::NodeCoord,
+                "DISPLAY_DATA_SECTION" => section = Section::DisplayData,
+                "EDGE_WEIGHT_SECTION" => section = Section::EdgeWeight,
+                "EOF" => break,
+                _ => section = Section::Unsupported,
+            }
+            continue;
+        }
+
+        match section {
+            Section::NodeCoord => {
+                instance.data.push(parse_node(line).map_err(at_line)?);
+                node_lines.push(line_number);
+            }
+            Section::DisplayData => {
+                display_data.push(parse_node(line).map_err(at_line)?);
+                display_lines.push(line_number);
+            }
+            Section::EdgeWeight => {
+                for item in line.split_whitespace() {
+                    let weight = item.parse::<f64>().map_err(|_| {
+                        at_line(LoadErrorKind::NonNumeric {
+                            column: "weight",
+                            value: item.to_string(),
+                        })
+                    })?;
+                    weights.push(weight);
+                }
+            }
+            Section::None | Section::Unsupported => {}
+        }
+    }
+
+    if instance.dimension == 0 {
+        instance.dimension = instance.data.len();
+    }
+    // Rows of an explicit matrix are addressed by id, so there the ids must
+    // be 1..=DIMENSION and the nodes are kept in id order.
+    let explicit = instance.edge_weight_type == Some(EdgeWeightType::Explicit);
+    let max_id = explicit.then_some(instance.dimension);
+    validate_ids(&instance.data, &node_lines, max_id)?;
+    validate_ids(&display_data, &display_lines, max_id)?;
+    if instance.data.is_empty() {
+        instance.data = display_data;
+    }
+    let mismatch = |expected, found| {
+        LoadError::at_line(
+            last_line,
+            LoadErrorKind::DimensionMismatch { expected, found },
+        )
+    };
+    if !instance.data.is_empty() && instance.data.len() != instance.dimension {
+        return Err(mismatch(instance.dimension, instance.data.len()));
+    }
+
+    if explicit {
+        // FULL_MATRIX is the only layout allowed without an explicit format.
+        let format = instance
+            .edge_weight_format
+            .unwrap_or(EdgeWeightFormat::FullMatrix);
+        let expected = format.weight_count(instance.dimension);
+        if weights.len() != expected {
+            return Err(mismatch(expected, weights.len()));
+        }
+        instance.edge_weights = Some(format.matrix(instance.dimension, &weights));
+        instance.data.sort_by_key(|node| node.group);
+    }
+
+    Ok(instance)
+}
+
+/// Writes `instance` in TSPLIB format. Explicit weights are always written
+/// as a `FULL_MATRIX`, and their nodes, if any, as `DISPLAY_DATA_SECTION`.
+pub fn format_tsplib(instance: &Instance) -> String {
+    let mut content = String::new();
+    let edge_weight_type = instance.edge_weight_type.unwrap_or(EdgeWeightType::Euc2D);
+    let problem_type = if instance.problem_type.is_empty() {
+        "TSP"
+    } else {
+        &instance.problem_type
+    };
+    writeln!(content, "NAME : {}", instance.name).unwrap();
+    writeln!(content, "TYPE : {}", problem_type).unwrap();
+    for comment in instance.comment.lines() {
+        writeln!(content, "COMMENT : {}", comment).unwrap();
+    }
+    let dimension = match &instance.edge_weights {
+        Some(weights) => weights.len(),
+        None => instance.data.len(),
+    };
+    writeln!(content, "DIMENSION : {}", dimension).unwrap();
+    writeln!(content, "EDGE_WEIGHT_TYPE : {}", edge_weight_type).unwrap();
+
+    let node_section = match &instance.edge_weights {
+        Some(weights) => {
+            writeln!(
+                content,
+                "EDGE_WEIGHT_FORMAT : {}",
+                EdgeWeightFormat::FullMatrix
+            )
+            .unwrap();
+            if !instance.data.is_empty() {
+                writeln!(content, "DISPLAY_DATA_TYPE : TWOD_DISPLAY").unwrap();
+            }
+            writeln!(content, "EDGE_WEIGHT_SECTION").unwrap();
+            for row in weights.iter() {
+                let row = row
+                    .iter()
+                    .map(|weight| weight.to_string())
+                    .collect::<Vec<_>>();
+                writeln!(content, "{}", row.join(" ")).unwrap();
+            }
+            "DISPLAY_DATA_SECTION"
+        }
+        None => "NODE_COORD_SECTION",
+    };
+    if !instance.data.is_empty() {
+        writeln!(content, "{}", node_section).unwrap();
+        for node in instance.data.iter() {
+            writeln!(content, "{} {} {}", node.group, node.point.x, node.point.y).unwrap();
+        }
+    }
+    writeln!(content, "EOF").unwrap();
+    content
+}
+
+pub fn write_tsplib<P: AsRef<Path>>(fp: P, instance: &Instance) -> std::io::Result<()> {
+    std::fs::write(fp, format_tsplib(instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::LoadErrorKind,
+        load,
+        tsplib::{format_tsplib, load_tsplib, parse_tsplib, EdgeWeightFormat, EdgeWeightType},
+    };
+
+    #[test]
+    fn raw_51_reads_specification() {
+        let instance = load_tsplib("../data/inst_51_raw.txt").unwrap();
+        assert_eq!(instance.name, "eil51");
+        assert_eq!(instance.problem_type, "TSP");
+        assert_eq!(instance.comment, "51-city problem (Christofides/Eilon)");
+        assert_eq!(instance.dimension, 51);
+        assert_eq!(instance.edge_weight_type, Some(EdgeWeightType::Euc2D));
+    }
+
+    #[test]
+    fn raw_100_matches_stripped_file() {
+        let instance = load_tsplib("../data/inst_100_raw.txt").unwrap();
+        let data = load("../data/inst_100.txt").unwrap();
+        assert_eq!(instance.name, "kroA100");
+        assert_eq!(instance.data.len(), instance.dimension);
+        for (raw, stripped) in instance.data.iter().zip(data.iter()) {
+            assert_eq!(raw.group, stripped.group);
+            assert_eq!(raw.point.x, stripped.point.x);
+            assert_eq!(raw.point.y, stripped.point.y);
+        }
+    }
+
+    #[test]
+    fn parses_without_eof_and_skips_unsupported_sections() {
+        let content = "NAME: tiny\nDIMENSION: 2\nEDGE_WEIGHT_TYPE: ATT\nNODE_COORD_SECTION\n1 0 0\n2 3 4\nDEPOT_SECTION\n1\n-1\n";
+        let instance = parse_tsplib(content).unwrap();
+        assert_eq!(instance.edge_weight_type, Some(EdgeWeightType::Att));
+        assert_eq!(instance.data.len(), 2);
+        assert_eq!(instance.data[1].point.y, 4.0);
+    }
+
+    #[test]
+    fn explicit_layouts_build_the_same_matrix() {
+        let expected = vec![
+            vec![0.0, 1.0, 2.0, 3.0],
+            vec![1.0, 0.0, 4.0, 5.0],
+            vec![2.0, 4.0, 0.0, 6.0],
+            vec![3.0, 5.0, 6.0, 0.0],
+        ];
+        let layouts = [
+            ("FULL_MATRIX", "0 1 2 3\n1 0 4 5\n2 4 0 6\n3 5 6 0"),
+            ("UPPER_ROW", "1 2 3\n4 5\n6"),
+            ("LOWER_ROW", "1\n2 4\n3 5 6"),
+            ("UPPER_DIAG_ROW", "0 1 2 3\n0 4 5\n0 6\n0"),
+            ("LOWER_DIAG_ROW", "0\n1 0\n2 4 0\n3 5 6 0"),
+            ("UPPER_COL", "1\n2 4\n3 5 6"),
+            ("LOWER_COL", "1 2 3\n4 5\n6"),
+            ("UPPER_DIAG_COL", "0\n1 0\n2 4 0\n3 5 6 0"),
+            ("LOWER_DIAG_COL", "0 1 2 3\n0 4 5\n0 6\n0"),
+        ];
+        for (format, section) in layouts {
+            let content = format!(
+                "NAME: gr4\nTYPE: TSP\nDIMENSION: 4\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: {}\nEDGE_WEIGHT_SECTION\n{}\nEOF\n",
+                format, section
+            );
+            let instance = parse_tsplib(&content).unwrap();
+            assert_eq!(
+                instance.edge_weight_format,
+                Some(format.parse::<EdgeWeightFormat>().unwrap())
+            );
+            assert_eq!(instance.edge_weights, Some(expected.clone()), "{}", format);
+            assert!(instance.data.is_empty());
+        }
+    }
+
+    #[test]
+    fn explicit_instance_keeps_display_data() {
+        let content = "NAME: tri\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: UPPER_ROW\nDISPLAY_DATA_TYPE: TWOD_DISPLAY\nEDGE_WEIGHT_SECTION\n 5 7\n 9\nDISPLAY_DATA_SECTION\n3 0 7\n1 0 0\n2 5 0\nEOF\n";
+        let instance = parse_tsplib(content).unwrap();
+        assert_eq!(instance.data.len(), 3);
+        assert_eq!(instance.data[2].group, 3);
+        assert_eq!(instance.data[2].point.y, 7.0);
+        assert_eq!(instance.edge_weights.unwrap()[1][2], 9.0);
+    }
+
+    #[test]
+    fn malformed_instances_are_errors() {
+        let err = parse_tsplib("NAME: x\nDIMENSION: two\n").unwrap_err();
+        assert_eq!(err.line, Some(2));
+        assert!(matches!(err.kind, LoadErrorKind::InvalidValue { .. }));
+
+        let err = parse_tsplib("EDGE_WEIGHT_TYPE: EUC_4D\n").unwrap_err();
+        assert!(matches!(err.kind, LoadErrorKind::InvalidValue { .. }));
+
+        let err =
+            parse_tsplib("DIMENSION: 2\nNODE_COORD_SECTION\n1 0 0\n0 1 1\nEOF\n").unwrap_err();
+        assert_eq!(err.line, Some(4));
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::IdOutOfRange { id: 0, max: None }
+        ));
+
+        let content = "DIMENSION: 2\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: UPPER_ROW\nEDGE_WEIGHT_SECTION\n1\nDISPLAY_DATA_SECTION\n1 0 0\n3 1 1\nEOF\n";
+        let err = parse_tsplib(content).unwrap_err();
+        assert_eq!(err.line, Some(8));
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::IdOutOfRange {
+                id: 3,
+                max: Some(2)
+            }
+        ));
+
+        let err =
+            parse_tsplib("DIMENSION: 3\nNODE_COORD_SECTION\n1 0 0\n2 1 1\nEOF\n").unwrap_err();
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::DimensionMismatch {
+                expected: 3,
+                found: 2
+            }
+        ));
+
+        let content = "DIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: UPPER_ROW\nEDGE_WEIGHT_SECTION\n1 2\nEOF\n";
+        let err = parse_tsplib(content).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            LoadErrorKind::DimensionMismatch {
+                expected: 3,
+                found: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn large_instance_with_sparse_ids() {
+        let mut content = String::from(
+            "NAME: big\nDIMENSION: 1000\nEDGE_WEIGHT_TYPE: EUC_2D\nNODE_COORD_SECTION\n",
+        );
+        for index in 0..1000 {
+            content.push_str(&format!("{} {} {}\n", 10 * index + 7, index, index % 31));
+        }
+        content.push_str("EOF\n");
+        let instance = parse_tsplib(&content).unwrap();
+        assert_eq!(instance.data.len(), 1000);
+        assert_eq!(instance.data[999].group, 9997);
+    }
+
+    #[test]
+    fn written_instances_read_back() {
+        let instance = load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let read = parse_tsplib(&format_tsplib(&instance)).unwrap();
+        assert_eq!(read.name, instance.name);
+        assert_eq!(read.comment, instance.comment);
+        assert_eq!(read.edge_weight_type, Some(EdgeWeightType::Euc2D));
+        assert_eq!(read.data.len(), 51);
+        assert_eq!(read.data[50].point.x, instance.data[50].point.x);
+
+        let content = "NAME: tri\nDIMENSION: 3\nEDGE_WEIGHT_TYPE: EXPLICIT\nEDGE_WEIGHT_FORMAT: UPPER_ROW\nEDGE_WEIGHT_SECTION\n 5 7.5\n 9\nEOF\n";
+        let instance = parse_tsplib(content).unwrap();
+        let written = format_tsplib(&instance);
+        assert!(written.contains("EDGE_WEIGHT_FORMAT : FULL_MATRIX"));
+        let read = parse_tsplib(&written).unwrap();
+        assert_eq!(read.edge_weights, instance.edge_weights);
+    }
+}
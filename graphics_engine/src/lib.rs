@@ -6,14 +6,14 @@ extern crate piston;
 use glutin_window::GlutinWindow as Window;
 use opengl_graphics::{GlGraphics, OpenGL};
 use piston::input::RenderArgs;
-use piston::window::WindowSettings;
+use piston::window::{AdvancedWindow, WindowSettings};
 use piston::{Event, EventSettings, Events, RenderEvent, UpdateArgs, UpdateEvent, CloseArgs, CloseEvent};
 
 pub struct App {
     gl: GlGraphics,
     pub window_handle: Window,
-    scene_height: usize,
-    scene_width: usize,
+    scene_min: (f64, f64),
+    scene_max: (f64, f64),
     state: Vec<Object>,
 }
 
@@ -23,7 +23,7 @@ pub enum ObjectType {
 }
 
 pub struct Object {
-    pos: Vec<(usize, usize)>,
+    pos: Vec<(f64, f64)>,
     color: [f32; 4],
     object_type: ObjectType,
 }
@@ -62,22 +62,19 @@ impl EventsBridge {
     }
     pub fn next(&mut self, window: &mut Window) -> Option<EventBridge> {
         let event = self.events.next(window);
-        return match event {
-            Some(e) => Some(EventBridge::create(e)),
-            None => None,
-        };
+        event.map(EventBridge::create)
     }
 }
 
 impl Object {
-    pub fn create_center(x: usize, y: usize, color: [f32; 4], object_type: ObjectType) -> Object {
+    pub fn create_center(x: f64, y: f64, color: [f32; 4], object_type: ObjectType) -> Object {
         Object {
             pos: [(x, y)].to_vec(),
             color,
             object_type,
         }
     }
-    pub fn create(points: Vec<(usize, usize)>, color: [f32; 4], object_type: ObjectType) -> Object {
+    pub fn create(points: Vec<(f64, f64)>, color: [f32; 4], object_type: ObjectType) -> Object {
         Object {
             pos: points,
             color,
@@ -86,6 +83,8 @@ impl Object {
     }
 }
 
+const SCENE_MARGIN: f64 = 0.05;
+
 pub const WHITE: graphics::types::Color = [1.0, 1.0, 1.0, 1.0];
 pub const RED: graphics::types::Color = [1.0, 0.0, 0.0, 1.0];
 pub const GREEN: graphics::types::Color = [0.0, 1.0, 0.0, 1.0];
@@ -93,7 +92,7 @@ pub const BLUE: graphics::types::Color = [0.0, 0.0, 1.0, 1.0];
 pub const BLACK: graphics::types::Color = [0.0, 0.0, 0.0, 1.0];
 
 impl App {
-    pub fn create(title: &'static str, scene_height: usize, scene_width: usize) -> Self {
+    pub fn create(title: &'static str, scene_min: (f64, f64), scene_max: (f64, f64)) -> Self {
         let opengl = OpenGL::V4_5;
 
         // Create a Glutin window.
@@ -106,18 +105,23 @@ impl App {
         App {
             gl: GlGraphics::new(opengl),
             window_handle: window,
-            scene_height,
-            scene_width,
+            scene_min,
+            scene_max,
             state: empty_state,
         }
     }
 
     pub fn render(&mut self, args: &RenderArgs) {
         let (win_width, win_height) = (args.window_size[0], args.window_size[1]);
-        let scene_height = self.scene_height + 1;
-        let scene_width = self.scene_width + 1;
-        let rect_width = win_width / scene_width as f64;
-        let rect_height = win_height / scene_height as f64;
+        // Keep a margin around the scene so points on its border stay visible.
+        let (min_x, min_y) = self.scene_min;
+        let (max_x, max_y) = self.scene_max;
+        let margin_x = (max_x - min_x).max(1.0) * SCENE_MARGIN;
+        let margin_y = (max_y - min_y).max(1.0) * SCENE_MARGIN;
+        let origin_x = min_x - margin_x;
+        let origin_y = min_y - margin_y;
+        let rect_width = win_width / (max_x - origin_x + margin_x);
+        let rect_height = win_height / (max_y - origin_y + margin_y);
         // println!(
         //     "{} {} {} {} {} {}",
         //     win_height, win_width, scene_height, scene_width, rect_height, rect_width
@@ -137,18 +141,18 @@ impl App {
                 match obj_type {
                     ObjectType::CIRCLE => {
                         let (i, j) = object.pos[0];
-                        let x = rect_width * i as f64;
-                        let y = rect_height * j as f64;
+                        let x = rect_width * (i - origin_x);
+                        let y = rect_height * (j - origin_y);
                         let circle = graphics::ellipse::circle(x, y, 6.0);
                         graphics::ellipse(color, circle, t, gl);
                     }
                     ObjectType::LINE => {
                         let (i_initial, j_initial) = object.pos[0];
                         let (i_final, j_final) = object.pos[1];
-                        let x_initial = rect_width * i_initial as f64;
-                        let y_initial = rect_height * j_initial as f64;
-                        let x_final = rect_width * i_final as f64;
-                        let y_final = rect_height * j_final as f64;
+                        let x_initial = rect_width * (i_initial - origin_x);
+                        let y_initial = rect_height * (j_initial - origin_y);
+                        let x_final = rect_width * (i_final - origin_x);
+                        let y_final = rect_height * (j_final - origin_y);
                         let line: graphics::types::Line = [x_initial, y_initial, x_final, y_final];
                         graphics::line(color, 2.0, line, t, gl);
                     }
@@ -157,6 +161,10 @@ impl App {
         });
     }
 
+    pub fn set_title(&mut self, title: String) {
+        self.window_handle.set_title(title);
+    }
+
     pub fn update(&mut self, _: &UpdateArgs, new_state: Vec<Object>) {
         self.state = new_state;
     }
@@ -1,10 +1,13 @@
 use std::{
     collections::HashMap,
-    sync::mpsc::{self, Sender},
+    sync::{
+        mpsc::{self, Sender},
+        Arc,
+    },
     thread::spawn,
 };
 
-use data_retrieve::DataNode;
+use data_retrieve::{best_known::BestKnownRegistry, error::LoadError, tsplib::Instance, DataNode};
 use tokio::{
     fs::File,
     io::{self, AsyncWriteExt},
@@ -12,8 +15,8 @@ use tokio::{
 
 use threadpool::ThreadPool;
 use tsa_sim::{
-    cooling_methods::{CoolingMethod, CosCooling, ExpCooling, SigmoidCooling},
-    TSAConfig, TSA,
+    cooling_methods::{DynCooling, Schedule},
+    distance_matrix, TSAConfig, TSA,
 };
 
 /**
@@ -32,12 +35,20 @@ struct Config {
     id: usize,
     method: &'static str,
     inst: usize,
+    seed: u64,
 }
 
 const QTD_ITERS_INITIAL: usize = 3_000_000;
 const TEMP_INITIAL_INITIAL: f64 = 800.0;
 const TEMP_FINAL_INITIAL: f64 = 1E-6;
 const QTD_ITERS_ON_TEMP_INITIAL: usize = 1;
+const SEED_BASE: u64 = 1_000;
+
+// Run `id` of every method and instance uses the same seed, so the methods
+// are compared on the same random streams.
+fn run_seed(id: usize) -> u64 {
+    SEED_BASE + id as u64
+}
 
 impl Config {
     pub fn create(id: usize, method: &'static str, inst: usize, qtd_on_temp: usize) -> Self {
@@ -49,6 +60,7 @@ impl Config {
             id,
             method,
             inst,
+            seed: run_seed(id),
         }
     }
 
@@ -61,6 +73,7 @@ impl Config {
             id: 0,
             method: "exp",
             inst: 100,
+            seed: run_seed(0),
         }
     }
 }
@@ -73,11 +86,10 @@ impl Iterator for Config {
         let method = self.method;
         let inst = self.inst;
         let qtd_on_temp = self.qtd_iters_on_temp;
-        let next_self: Config;
         if method == "b" {
             return None;
         }
-        if id >= 50 {
+        let next_self = if id >= 50 {
             let (m, instance, qtd_on_iter) = if method == "exp" {
                 ("sigmoid", inst, qtd_on_temp)
             } else if method == "sigmoid" {
@@ -92,76 +104,110 @@ impl Iterator for Config {
             else {
                 ("exp", 100, 10)
             };
-            next_self = Config::create(0, m, instance, qtd_on_iter);
+            Config::create(0, m, instance, qtd_on_iter)
         } else {
-            next_self = Config::create(id, method, inst, qtd_on_temp);
-        }
-        let r = Some(self.clone());
+            Config::create(id, method, inst, qtd_on_temp)
+        };
+        let r = Some(*self);
         self.id = next_self.id;
         self.method = next_self.method;
         self.inst = next_self.inst;
         self.qtd_iters_on_temp = next_self.qtd_iters_on_temp;
-        return r;
+        self.seed = next_self.seed;
+        r
     }
 }
 
 struct ChannelData {
     distance: f64,
+    gap: Option<f64>,
     method: &'static str,
     inst: usize,
     qtd_on_iter: usize,
+    seed: u64,
+    best_distance: f64,
+    best_gap: Option<f64>,
+    best_iter: usize,
 }
 
-fn worker<T: CoolingMethod + 'static>(
+fn worker(
     sender: &Sender<ChannelData>,
     config: Config,
-    data: &Vec<DataNode>,
+    schedule: Schedule,
+    data: &[DataNode],
+    distances: &Arc<Vec<Vec<f64>>>,
+    best_known: Option<f64>,
     pool: &ThreadPool,
 ) {
     // for id in 0..qtd_jobs {
     let sender_clone = sender.clone();
-    let data_clone = data.clone();
-    let config_clone = config.clone();
+    let data_clone = data.to_vec();
+    let distances = Arc::clone(distances);
+    let config_clone = config;
     pool.execute(move || {
-        dbg!(&config_clone);
-        let sim_config = TSAConfig::<T>::create(
+        let sim_config = TSAConfig::<DynCooling>::create(
             config_clone.temp_final,
             config_clone.temp_initial,
             config_clone.qtd_iters,
             config_clone.qtd_iters_on_temp,
-        );
-        let mut sim = TSA::create(data_clone, sim_config);
+        )
+        .with_schedule(schedule)
+        .with_seed(config_clone.seed);
+        let mut sim = TSA::create_shared(data_clone, distances, sim_config);
         for _ in 0..config.qtd_iters {
             sim.gen_next_solution();
         }
         sender_clone
             .send(ChannelData {
                 distance: sim.get_current_distance(),
+                gap: best_known.map(|best_known| sim.get_gap(best_known)),
                 method: config_clone.method,
                 inst: config_clone.inst,
                 qtd_on_iter: config_clone.qtd_iters_on_temp,
+                seed: sim.get_seed(),
+                best_distance: sim.get_best_distance(),
+                best_gap: best_known.map(|best_known| sim.get_best_gap(best_known)),
+                best_iter: sim.get_best_iter(),
             })
             .unwrap();
-        dbg!(&config_clone);
-        dbg!("Job done");
+        println!(
+            "done: {} on inst_{}, {} iters on temp, run {}",
+            config_clone.method, config_clone.inst, config_clone.qtd_iters_on_temp, config_clone.id
+        );
     });
     // }
 }
 
+fn invalid_data(err: LoadError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn load_instance(fp: &str) -> io::Result<Instance> {
+    data_retrieve::tsplib::load_tsplib(fp).map_err(invalid_data)
+}
+
 #[tokio::main]
 async fn main() -> io::Result<()> {
     let num_workers = 12usize;
     let pool = ThreadPool::new(num_workers);
 
-    let data = data_retrieve::load("data/inst_100.txt");
-    let data_51 = data_retrieve::load("data/inst_51.txt");
+    let instance = load_instance("data/inst_100_raw.txt")?;
+    let instance_51 = load_instance("data/inst_51_raw.txt")?;
+    let registry = BestKnownRegistry::load("best_solution_dist.txt").map_err(invalid_data)?;
+    let best_known = registry.distance(&instance.name);
+    let best_known_51 = registry.distance(&instance_51.name);
+    // Built once per instance and shared by all of its jobs.
+    let distances = Arc::new(distance_matrix(&instance).map_err(invalid_data)?);
+    let distances_51 = Arc::new(distance_matrix(&instance_51).map_err(invalid_data)?);
+    let data = instance.data;
+    let data_51 = instance_51.data;
 
     let configs = Config::create_first();
     let (sender, receiver) = mpsc::channel::<ChannelData>();
 
     let mut files: HashMap<String, File> = HashMap::new();
     let mut data_queue: HashMap<String, Vec<u8>> = HashMap::new();
-    for met in ["exp", "cos", "sigmoid"] {
+    for met in [Schedule::Exp, Schedule::Cos, Schedule::Sigmoid] {
         for inst in [51, 100] {
             for on_temp in [1, 10] {
                 let fp = format!("data/runs/inst_{}_{}_on_temp_{}.txt", inst, met, on_temp);
@@ -175,22 +221,15 @@ async fn main() -> io::Result<()> {
 
     let h = spawn(move || {
         for config in configs {
-            let data_clone = if config.inst == 100 {
-                data.clone()
+            let (data, distances, best_known) = if config.inst == 100 {
+                (&data, &distances, best_known)
             } else {
-                data_51.clone()
+                (&data_51, &distances_51, best_known_51)
             };
-            match config.method {
-                "exp" => {
-                    worker::<ExpCooling>(&sender, config, &data_clone, &pool);
-                }
-                "cos" => {
-                    worker::<CosCooling>(&sender, config, &data_clone, &pool);
-                }
-                "sigmoid" => {
-                    worker::<SigmoidCooling>(&sender, config, &data_clone, &pool);
-                }
-                _ => {}
+            if let Some(schedule) = Schedule::from_name(config.method) {
+                worker(
+                    &sender, config, schedule, data, distances, best_known, &pool,
+                );
             }
             // dbg!(config);
         }
@@ -203,12 +242,23 @@ async fn main() -> io::Result<()> {
         );
         // dbg!(fp.clone());
         let data_q = data_queue.get_mut(&fp).unwrap();
-        let data_string = format!("{}\n", data.distance);
+        // distance gap seed best_distance best_gap best_iter, with `-` for
+        // gaps when there is no best-known value
+        let gap = |gap: Option<f64>| gap.map_or(String::from("-"), |gap| gap.to_string());
+        let data_string = format!(
+            "{} {} {} {} {} {}\n",
+            data.distance,
+            gap(data.gap),
+            data.seed,
+            data.best_distance,
+            gap(data.best_gap),
+            data.best_iter
+        );
         data_q.extend(data_string.as_bytes());
     }
     for (key, data_q) in data_queue.iter() {
         let fd = files.get_mut(key).unwrap();
-        fd.write(data_q).await?;
+        fd.write_all(data_q).await?;
     }
     h.join().unwrap();
     // dbg!(data_queue);
@@ -0,0 +1,113 @@
+use data_retrieve::{best_known::BestKnownRegistry, format::load_instance, tour::write_tour};
+use std::{env, process, str::FromStr, thread, time::Instant};
+use tsa_sim::{
+    calibration::Calibration,
+    cooling_methods::{DynCooling, Schedule},
+    gap,
+    islands::IslandModel,
+    moves::Operator,
+    selection::OperatorSelection,
+    TSAConfig,
+};
+
+const USAGE: &str = "usage: islands <instance> [--chains n] [--iters n] [--migration n] [--cooling schedule] [--seed u64] [--save-tour file.tour]";
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn parse<T: FromStr>(flag: &str, value: Option<String>) -> T {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| fail(&format!("{} takes a number\n{}", flag, USAGE)))
+}
+
+fn main() {
+    let mut fp: Option<String> = None;
+    let mut chains = thread::available_parallelism().map_or(1, |cores| cores.get());
+    let mut qtd_iters = 3000000;
+    let mut migration_interval = 10000;
+    let mut schedule = Schedule::Exp;
+    let mut seed: Option<u64> = None;
+    let mut save_tour: Option<String> = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--chains" => chains = parse("--chains", args.next()),
+            "--iters" => qtd_iters = parse("--iters", args.next()),
+            "--migration" => migration_interval = parse("--migration", args.next()),
+            "--seed" => seed = Some(parse("--seed", args.next())),
+            "--cooling" => {
+                let name = args.next().unwrap_or_default();
+                schedule = Schedule::from_name(&name).unwrap_or_else(|| {
+                    let names = Schedule::ALL.map(|schedule| schedule.to_string());
+                    fail(&format!("--cooling takes one of {}", names.join(", ")))
+                });
+            }
+            "--save-tour" => save_tour = args.next(),
+            _ => fp = Some(arg),
+        }
+    }
+    let fp = fp.unwrap_or_else(|| fail(USAGE));
+    if chains == 0 {
+        fail("--chains takes at least one chain");
+    }
+    let instance = load_instance(&fp, None).unwrap_or_else(|err| fail(&err.to_string()));
+    let name = instance.name.clone();
+
+    let config = |chain: usize| {
+        let config = TSAConfig::<DynCooling>::create(20.0, 800.0, qtd_iters, 10)
+            .with_schedule(schedule)
+            .with_operators(
+                Operator::ALL
+                    .iter()
+                    .map(|operator| (*operator, 1.0))
+                    .collect(),
+            )
+            .with_operator_selection(OperatorSelection::Adaptive { reaction: 0.1 })
+            .with_calibration(Calibration::default());
+        match seed {
+            Some(seed) => config.with_seed(seed.wrapping_add(chain as u64)),
+            None => config,
+        }
+    };
+    let start = Instant::now();
+    let mut islands = IslandModel::from_instance(instance, chains, migration_interval, config)
+        .unwrap_or_else(|err| fail(&err.with_file(&fp).to_string()));
+    let reasons = islands.run();
+    let elapsed = start.elapsed();
+
+    println!("{} chains on {} in {:?}", chains, name, elapsed);
+    for (index, (chain, reason)) in islands.get_chains().iter().zip(reasons).enumerate() {
+        println!(
+            "chain {:>3}: best {:.2}, adopted {} times, stopped after {}",
+            index,
+            chain.get_best_distance(),
+            islands.get_adoptions()[index],
+            reason
+        );
+    }
+    let best = islands.get_best_distance();
+    let mut registry = BestKnownRegistry::load("best_solution_dist.txt").unwrap_or_else(|err| {
+        eprintln!("No best-known values: {}", err);
+        BestKnownRegistry::new()
+    });
+    if let Err(err) = registry.add_tour_beside(&fp) {
+        eprintln!("Could not load the optimal tour: {}", err);
+    }
+    let best_known = registry
+        .get(&name)
+        .and_then(|best_known| islands.get_chains()[0].get_best_known_distance(best_known));
+    match best_known {
+        Some(best_known) => println!("best {:.2} (gap {:.2}%)", best, gap(best, best_known)),
+        None => println!("best {:.2}", best),
+    }
+
+    if let Some(save_tour) = save_tour {
+        let tour = islands.get_best_tour(&format!("{}.tour", name));
+        if let Err(err) = write_tour(&save_tour, &tour) {
+            fail(&format!("Could not save tour to {}: {}", save_tour, err));
+        }
+    }
+}
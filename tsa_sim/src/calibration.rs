@@ -0,0 +1,88 @@
+/// Classic choice for the share of uphill moves accepted at the start.
+const INITIAL_ACCEPTANCE: f64 = 0.8;
+/// Share of average uphill moves still accepted at the end.
+const FINAL_ACCEPTANCE: f64 = 1e-3;
+const SAMPLES: usize = 1000;
+
+/// Derives the temperatures of a run from the instance instead of taking
+/// them from the config: random moves are drawn from the start tour, and the
+/// temperatures are set so an average uphill move is accepted with the given
+/// probabilities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Probability, in `0.0..1.0`, of accepting an average uphill move at
+    /// `initial_temperature`.
+    pub initial_acceptance: f64,
+    /// Same, at `final_temperature`; below `initial_acceptance`.
+    pub final_acceptance: f64,
+    /// Moves sampled.
+    pub samples: usize,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            initial_acceptance: INITIAL_ACCEPTANCE,
+            final_acceptance: FINAL_ACCEPTANCE,
+            samples: SAMPLES,
+        }
+    }
+}
+
+impl Calibration {
+    /// Initial and final temperatures for the sampled move `deltas`, or
+    /// `None` if none of them was uphill. Panics unless
+    /// `0 < final_acceptance < initial_acceptance < 1`.
+    pub fn temperatures(&self, deltas: &[f64]) -> Option<(f64, f64)> {
+        assert!(
+            0.0 < self.final_acceptance
+                && self.final_acceptance < self.initial_acceptance
+                && self.initial_acceptance < 1.0,
+            "Invalid acceptance targets: initial {}, final {}",
+            self.initial_acceptance,
+            self.final_acceptance
+        );
+        let uphill = deltas
+            .iter()
+            .copied()
+            .filter(|delta| *delta > 0.0)
+            .collect::<Vec<_>>();
+        if uphill.is_empty() {
+            return None;
+        }
+        let mean = uphill.iter().sum::<f64>() / uphill.len() as f64;
+        // exp(-mean / T) = acceptance
+        let temperature = |acceptance: f64| -mean / acceptance.ln();
+        Some((
+            temperature(self.initial_acceptance),
+            temperature(self.final_acceptance),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calibration::Calibration;
+
+    #[test]
+    fn temperatures_hit_the_target_acceptance() {
+        let calibration = Calibration::default();
+        let deltas = [-5.0, 10.0, 30.0, 0.0, 20.0];
+        let (initial, fin) = calibration.temperatures(&deltas).unwrap();
+        assert!(((-20.0 / initial).exp() - calibration.initial_acceptance).abs() < 1e-9);
+        assert!(((-20.0 / fin).exp() - calibration.final_acceptance).abs() < 1e-9);
+        assert!(initial > fin);
+        assert_eq!(calibration.temperatures(&[-1.0, 0.0]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid acceptance targets")]
+    fn final_acceptance_must_be_below_the_initial_one() {
+        let calibration = Calibration {
+            initial_acceptance: 0.5,
+            final_acceptance: 0.8,
+            ..Calibration::default()
+        };
+        calibration.temperatures(&[10.0]);
+    }
+}
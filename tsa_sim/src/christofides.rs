@@ -0,0 +1,110 @@
+use crate::matching::min_weight_perfect_matching;
+
+/// Christofides' tour over every node of `distances`: a minimum spanning
+/// tree, a minimum-weight perfect matching on its odd-degree nodes, an
+/// Eulerian circuit of both, shortcut past repeated nodes. On metric
+/// instances it is at most 1.5 times the optimum.
+pub fn christofides(distances: &[Vec<f64>]) -> Vec<usize> {
+    let size = distances.len();
+    if size < 3 {
+        return (0..size).collect();
+    }
+    let mut adjacency = vec![vec![]; size];
+    for (a, b) in minimum_spanning_tree(distances) {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    let odd = (0..size)
+        .filter(|node| adjacency[*node].len() % 2 == 1)
+        .collect::<Vec<_>>();
+    for (a, b) in min_weight_perfect_matching(distances, &odd) {
+        adjacency[a].push(b);
+        adjacency[b].push(a);
+    }
+    shortcut(&eulerian_circuit(adjacency), size)
+}
+
+// Prim's algorithm on the complete graph, O(n^2).
+fn minimum_spanning_tree(distances: &[Vec<f64>]) -> Vec<(usize, usize)> {
+    let size = distances.len();
+    let mut in_tree = vec![false; size];
+    let mut closest = vec![(f64::INFINITY, 0); size];
+    let mut edges = Vec::with_capacity(size - 1);
+    let mut node = 0;
+    in_tree[node] = true;
+    for _ in 1..size {
+        for other in 0..size {
+            if !in_tree[other] && distances[node][other] < closest[other].0 {
+                closest[other] = (distances[node][other], node);
+            }
+        }
+        node = (0..size)
+            .filter(|other| !in_tree[*other])
+            .min_by(|a, b| closest[*a].0.total_cmp(&closest[*b].0))
+            .unwrap();
+        in_tree[node] = true;
+        edges.push((closest[node].1, node));
+    }
+    edges
+}
+
+// Hierholzer's algorithm; `adjacency` is a connected multigraph with every
+// degree even.
+fn eulerian_circuit(mut adjacency: Vec<Vec<usize>>) -> Vec<usize> {
+    let mut circuit = vec![];
+    let mut stack = vec![0];
+    while let Some(&node) = stack.last() {
+        match adjacency[node].pop() {
+            Some(next) => {
+                let back = adjacency[next]
+                    .iter()
+                    .position(|other| *other == node)
+                    .unwrap();
+                adjacency[next].swap_remove(back);
+                stack.push(next);
+            }
+            None => circuit.push(stack.pop().unwrap()),
+        }
+    }
+    circuit
+}
+
+fn shortcut(circuit: &[usize], size: usize) -> Vec<usize> {
+    let mut visited = vec![false; size];
+    circuit
+        .iter()
+        .copied()
+        .filter(|node| !std::mem::replace(&mut visited[*node], true))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use data_retrieve::tsplib::load_tsplib;
+
+    use crate::{christofides::christofides, metric::Metric};
+
+    #[test]
+    fn within_the_approximation_bound() {
+        let instance = load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let distances = instance
+            .data
+            .iter()
+            .map(|a| {
+                instance
+                    .data
+                    .iter()
+                    .map(|b| Metric::Euc2D.distance(&a.point, &b.point))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let solution = christofides(&distances);
+        let mut sorted = solution.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..distances.len()).collect::<Vec<_>>());
+        let length = (0..solution.len())
+            .map(|index| distances[solution[index]][solution[(index + 1) % solution.len()]])
+            .sum::<f64>();
+        assert!(length <= 426.0 * 1.5, "{}", length);
+    }
+}
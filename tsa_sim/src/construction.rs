@@ -0,0 +1,481 @@
+use data_retrieve::Data;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+
+use crate::christofides::christofides;
+
+/// How the tour the annealing starts from is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Construction {
+    /// A random permutation.
+    #[default]
+    Random,
+    /// From a random node, always on to the closest unvisited node.
+    NearestNeighbor,
+    /// Shortest edges first, skipping those that would close a cycle early
+    /// or give a node a third edge.
+    GreedyEdge,
+    /// From a random node, the node and place that lengthen the tour the
+    /// least.
+    CheapestInsertion,
+    /// From a random node, the node farthest from the tour, at its cheapest
+    /// place.
+    FarthestInsertion,
+    /// From a random node, the node closest to the tour, at its cheapest
+    /// place.
+    NearestInsertion,
+    /// The convex hull of the coordinates, then cheapest insertion. Falls
+    /// back to [`Construction::CheapestInsertion`] without coordinates.
+    ConvexHull,
+    /// The nodes in the order a Hilbert curve visits them. Falls back to
+    /// [`Construction::NearestNeighbor`] without coordinates.
+    SpaceFillingCurve,
+    /// Christofides' tree-and-matching tour, within 1.5 times the optimum
+    /// on metric instances.
+    Christofides,
+}
+
+impl Construction {
+    pub const ALL: [Construction; 9] = [
+        Construction::Random,
+        Construction::NearestNeighbor,
+        Construction::GreedyEdge,
+        Construction::CheapestInsertion,
+        Construction::FarthestInsertion,
+        Construction::NearestInsertion,
+        Construction::ConvexHull,
+        Construction::SpaceFillingCurve,
+        Construction::Christofides,
+    ];
+
+    /// A tour over every node of `distances`. `data` holds their coordinates,
+    /// in the same order, or is empty.
+    pub fn build(&self, distances: &[Vec<f64>], data: &Data, rng: &mut StdRng) -> Vec<usize> {
+        let size = distances.len();
+        if size == 0 {
+            return vec![];
+        }
+        let has_coordinates = data.len() == size;
+        match self {
+            Construction::Random => {
+                let mut solution = (0..size).collect::<Vec<usize>>();
+                solution.shuffle(rng);
+                solution
+            }
+            Construction::NearestNeighbor => nearest_neighbor(distances, rng.gen_range(0..size)),
+            Construction::GreedyEdge => greedy_edge(distances),
+            Construction::CheapestInsertion => {
+                cheapest_insertion(distances, vec![rng.gen_range(0..size)])
+            }
+            Construction::FarthestInsertion => {
+                selected_insertion(distances, rng.gen_range(0..size), true)
+            }
+            Construction::NearestInsertion => {
+                selected_insertion(distances, rng.gen_range(0..size), false)
+            }
+            Construction::ConvexHull if has_coordinates => {
+                cheapest_insertion(distances, convex_hull(data))
+            }
+            Construction::ConvexHull => Construction::CheapestInsertion.build(distances, data, rng),
+            Construction::SpaceFillingCurve if has_coordinates => hilbert_order(data),
+            Construction::SpaceFillingCurve => {
+                Construction::NearestNeighbor.build(distances, data, rng)
+            }
+            Construction::Christofides => christofides(distances),
+        }
+    }
+}
+
+fn nearest_neighbor(distances: &[Vec<f64>], start: usize) -> Vec<usize> {
+    let size = distances.len();
+    let mut visited = vec![false; size];
+    let mut solution = Vec::with_capacity(size);
+    let mut current = start;
+    visited[current] = true;
+    solution.push(current);
+    while solution.len() < size {
+        let next = (0..size)
+            .filter(|node| !visited[*node])
+            .min_by(|a, b| distances[current][*a].total_cmp(&distances[current][*b]))
+            .unwrap();
+        visited[next] = true;
+        solution.push(next);
+        current = next;
+    }
+    solution
+}
+
+fn find(parents: &mut [usize], node: usize) -> usize {
+    let mut root = node;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut node = node;
+    while parents[node] != root {
+        let parent = parents[node];
+        parents[node] = root;
+        node = parent;
+    }
+    root
+}
+
+fn greedy_edge(distances: &[Vec<f64>]) -> Vec<usize> {
+    let size = distances.len();
+    if size < 3 {
+        return (0..size).collect();
+    }
+    let mut edges = (0..size)
+        .flat_map(|a| (a + 1..size).map(move |b| (a, b)))
+        .collect::<Vec<_>>();
+    edges.sort_by(|(a, b), (c, d)| distances[*a][*b].total_cmp(&distances[*c][*d]));
+
+    let mut parents = (0..size).collect::<Vec<usize>>();
+    let mut neighbors = vec![vec![]; size];
+    let mut added = 0;
+    for (a, b) in edges {
+        if added == size - 1 {
+            break;
+        }
+        if neighbors[a].len() == 2 || neighbors[b].len() == 2 {
+            continue;
+        }
+        let (root_a, root_b) = (find(&mut parents, a), find(&mut parents, b));
+        if root_a == root_b {
+            continue;
+        }
+        parents[root_a] = root_b;
+        neighbors[a].push(b);
+        neighbors[b].push(a);
+        added += 1;
+    }
+
+    // The edges now form a single path; walk it from one of its ends.
+    let start = (0..size).find(|node| neighbors[*node].len() < 2).unwrap();
+    let mut solution = vec![start];
+    let mut previous = start;
+    let mut current = neighbors[start][0];
+    while current != start && solution.len() < size {
+        solution.push(current);
+        let next = neighbors[current]
+            .iter()
+            .copied()
+            .find(|node| *node != previous);
+        match next {
+            Some(next) => {
+                previous = current;
+                current = next;
+            }
+            None => break,
+        }
+    }
+    solution
+}
+
+// The tour as successor links, for insertion heuristics.
+fn tour_links(tour: &[usize], size: usize) -> Vec<usize> {
+    let mut next = vec![usize::MAX; size];
+    for (index, node) in tour.iter().enumerate() {
+        next[*node] = tour[(index + 1) % tour.len()];
+    }
+    next
+}
+
+fn walk_links(next: &[usize], start: usize) -> Vec<usize> {
+    let mut solution = vec![start];
+    let mut current = next[start];
+    while current != start {
+        solution.push(current);
+        current = next[current];
+    }
+    solution
+}
+
+fn insertion_cost(distances: &[Vec<f64>], a: usize, b: usize, node: usize) -> f64 {
+    distances[a][node] + distances[node][b] - distances[a][b]
+}
+
+// The tour node after which `node` is cheapest to insert, and the cost.
+fn cheapest_place(
+    distances: &[Vec<f64>],
+    next: &[usize],
+    start: usize,
+    node: usize,
+) -> (usize, f64) {
+    let mut best = (start, f64::INFINITY);
+    let mut current = start;
+    loop {
+        let cost = insertion_cost(distances, current, next[current], node);
+        if cost < best.1 {
+            best = (current, cost);
+        }
+        current = next[current];
+        if current == start {
+            return best;
+        }
+    }
+}
+
+fn cheapest_insertion(distances: &[Vec<f64>], initial: Vec<usize>) -> Vec<usize> {
+    let size = distances.len();
+    let start = initial[0];
+    let mut next = tour_links(&initial, size);
+    let mut pending = (0..size)
+        .filter(|node| next[*node] == usize::MAX)
+        .collect::<Vec<usize>>();
+    let mut places = pending
+        .iter()
+        .map(|node| cheapest_place(distances, &next, start, *node))
+        .collect::<Vec<_>>();
+
+    while !pending.is_empty() {
+        let chosen = (0..pending.len())
+            .min_by(|a, b| places[*a].1.total_cmp(&places[*b].1))
+            .unwrap();
+        let node = pending.swap_remove(chosen);
+        let (a, _) = places.swap_remove(chosen);
+        let b = next[a];
+        next[a] = node;
+        next[node] = b;
+
+        // Only the edge after `a` was removed; everyone else just compares
+        // against the two new edges.
+        for (pending_node, place) in pending.iter().zip(places.iter_mut()) {
+            if place.0 == a {
+                *place = cheapest_place(distances, &next, start, *pending_node);
+                continue;
+            }
+            for edge_start in [a, node] {
+                let cost = insertion_cost(distances, edge_start, next[edge_start], *pending_node);
+                if cost < place.1 {
+                    *place = (edge_start, cost);
+                }
+            }
+        }
+    }
+    walk_links(&next, start)
+}
+
+fn selected_insertion(distances: &[Vec<f64>], start: usize, farthest: bool) -> Vec<usize> {
+    let size = distances.len();
+    let mut next = vec![usize::MAX; size];
+    next[start] = start;
+    let mut to_tour = distances[start].clone();
+    let mut pending = (0..size).filter(|node| *node != start).collect::<Vec<_>>();
+
+    while !pending.is_empty() {
+        let by_distance =
+            |a: &usize, b: &usize| to_tour[pending[*a]].total_cmp(&to_tour[pending[*b]]);
+        let chosen = if farthest {
+            (0..pending.len()).max_by(by_distance)
+        } else {
+            (0..pending.len()).min_by(by_distance)
+        }
+        .unwrap();
+        let node = pending.swap_remove(chosen);
+        let (a, _) = cheapest_place(distances, &next, start, node);
+        next[node] = next[a];
+        next[a] = node;
+        for pending_node in pending.iter() {
+            to_tour[*pending_node] = to_tour[*pending_node].min(distances[node][*pending_node]);
+        }
+    }
+    walk_links(&next, start)
+}
+
+fn cross(o: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+// Andrew's monotone chain, as indices into `data` in counter-clockwise order.
+fn convex_hull(data: &Data) -> Vec<usize> {
+    let point = |index: usize| (data[index].point.x, data[index].point.y);
+    let mut order = (0..data.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| {
+        let (a, b) = (point(*a), point(*b));
+        a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+    });
+    if order.len() < 3 {
+        return order;
+    }
+
+    let mut hull: Vec<usize> = vec![];
+    for pass in 0..2 {
+        let floor = hull.len() + 1;
+        let nodes: Vec<usize> = if pass == 0 {
+            order.clone()
+        } else {
+            order.iter().rev().copied().collect()
+        };
+        for node in nodes {
+            while hull.len() > floor
+                && cross(
+                    point(hull[hull.len() - 2]),
+                    point(hull[hull.len() - 1]),
+                    point(node),
+                ) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(node);
+        }
+        // The last point of each chain starts the other one.
+        hull.pop();
+    }
+    hull.dedup();
+    hull
+}
+
+const HILBERT_ORDER: u32 = 16;
+
+// Position of cell (x, y) along a Hilbert curve over a 2^16 x 2^16 grid.
+fn hilbert_index(mut x: u64, mut y: u64) -> u64 {
+    let mut index = 0;
+    let mut side = 1u64 << (HILBERT_ORDER - 1);
+    while side > 0 {
+        let rx = u64::from(x & side > 0);
+        let ry = u64::from(y & side > 0);
+        index += side * side * ((3 * rx) ^ ry);
+        if ry == 0 {
+            if rx == 1 {
+                x = side - 1 - (x & (side - 1));
+                y = side - 1 - (y & (side - 1));
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x &= side - 1;
+        y &= side - 1;
+        side >>= 1;
+    }
+    index
+}
+
+fn hilbert_order(data: &Data) -> Vec<usize> {
+    let min_x = data
+        .iter()
+        .map(|node| node.point.x)
+        .fold(f64::MAX, f64::min);
+    let min_y = data
+        .iter()
+        .map(|node| node.point.y)
+        .fold(f64::MAX, f64::min);
+    let max_x = data
+        .iter()
+        .map(|node| node.point.x)
+        .fold(f64::MIN, f64::max);
+    let max_y = data
+        .iter()
+        .map(|node| node.point.y)
+        .fold(f64::MIN, f64::max);
+    let span = (max_x - min_x).max(max_y - min_y).max(f64::MIN_POSITIVE);
+    let cells = ((1u64 << HILBERT_ORDER) - 1) as f64;
+
+    let mut order = data
+        .iter()
+        .enumerate()
+        .map(|(index, node)| {
+            let x = ((node.point.x - min_x) / span * cells) as u64;
+            let y = ((node.point.y - min_y) / span * cells) as u64;
+            (hilbert_index(x, y), index)
+        })
+        .collect::<Vec<_>>();
+    order.sort();
+    order.into_iter().map(|(_, index)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use data_retrieve::{generator::Generator, tsplib::load_tsplib, DataNode};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use crate::{construction::Construction, metric::Metric, TSAConfig, TSA};
+
+    fn distances(data: &[DataNode]) -> Vec<Vec<f64>> {
+        data.iter()
+            .map(|a| {
+                data.iter()
+                    .map(|b| Metric::Euc2D.distance(&a.point, &b.point))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn length(distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        (0..solution.len())
+            .map(|index| distances[solution[index]][solution[(index + 1) % solution.len()]])
+            .sum()
+    }
+
+    #[test]
+    fn every_strategy_builds_a_tour() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for size in [0, 1, 2, 3, 7, 60] {
+            let data = Generator::Uniform {
+                size,
+                width: 100.0,
+                height: 100.0,
+            }
+            .generate(size as u64);
+            let distances = distances(&data);
+            for construction in Construction::ALL {
+                for data in [data.clone(), vec![]] {
+                    let mut solution = construction.build(&distances, &data, &mut rng);
+                    solution.sort();
+                    assert_eq!(
+                        solution,
+                        (0..size).collect::<Vec<_>>(),
+                        "{:?}",
+                        construction
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn heuristics_beat_a_random_start() {
+        let instance = load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let distances = distances(&instance.data);
+        let mut rng = StdRng::seed_from_u64(1);
+        let random = length(
+            &distances,
+            &Construction::Random.build(&distances, &instance.data, &mut rng),
+        );
+        for construction in &Construction::ALL[1..] {
+            let solution = construction.build(&distances, &instance.data, &mut rng);
+            let distance = length(&distances, &solution);
+            assert!(distance < random / 2.0, "{:?} {}", construction, distance);
+            // Nearest neighbour is within 25% of the optimum on average,
+            // insertion heuristics do better.
+            assert!(distance < 426.0 * 1.5, "{:?} {}", construction, distance);
+        }
+    }
+
+    #[test]
+    fn convex_hull_solves_points_on_a_circle() {
+        let generator = Generator::Circle {
+            size: 40,
+            radius: 1000.0,
+        };
+        let data = generator.generate(3);
+        let distances = distances(&data);
+        let mut rng = StdRng::seed_from_u64(0);
+        let solution = Construction::ConvexHull.build(&distances, &data, &mut rng);
+        let optimum = generator.known_optimum().unwrap();
+        assert_eq!(length(&distances, &solution), optimum);
+    }
+
+    #[test]
+    fn config_picks_the_initial_tour() {
+        let instance = load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let distances = distances(&instance.data);
+        for construction in Construction::ALL {
+            let config = TSAConfig::create_default()
+                .with_seed(9)
+                .with_construction(construction);
+            let tsa = TSA::from_instance(instance.clone(), config).unwrap();
+            let expected =
+                construction.build(&distances, &instance.data, &mut StdRng::seed_from_u64(9));
+            assert_eq!(tsa.solution, expected);
+            assert_eq!(tsa.get_best_distance(), length(&distances, &expected));
+        }
+    }
+}
@@ -1,8 +1,183 @@
-use std::f64::consts::PI;
+use std::{
+    f64::consts::{E, PI},
+    fmt::{self, Display},
+};
+
+/// How sharp [`TanhCooling`]'s drop is: the curve spans `tanh(-x..x)` for
+/// `x` half of this.
+const TANH_STEEPNESS: f64 = 10.0;
+/// Aarts and van Laarhoven's distance parameter: smaller cools slower and
+/// keeps the chain closer to equilibrium.
+const ADAPTIVE_DISTANCE: f64 = 0.1;
+/// Moves [`AdaptiveCooling`] pools, over as many plateaus as it takes,
+/// before it trusts the spread of the tour lengths.
+const ADAPTIVE_MIN_SAMPLES: usize = 1000;
 
 pub trait CoolingMethod {
     fn get_next_temperature(&self, current_iter: usize) -> f64;
-    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self;
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self
+    where
+        Self: Sized;
+
+    /// Moves the schedule onto new end temperatures, keeping its shape.
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64);
+
+    /// The temperature after the plateau `stats` describes. Schedules that
+    /// only depend on the iteration keep the default.
+    fn next_temperature(&mut self, stats: &PlateauStats) -> f64 {
+        self.get_next_temperature(stats.iter)
+    }
+}
+
+/// What the chain did while the temperature was held.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlateauStats {
+    /// Iteration the plateau ended at.
+    pub iter: usize,
+    pub temperature: f64,
+    pub tries: usize,
+    pub acceptances: usize,
+    /// Mean length of the tours visited, one per try.
+    pub mean_distance: f64,
+    // Sum of squared deviations from the mean (Welford).
+    squared_deviations: f64,
+}
+
+impl PlateauStats {
+    pub fn record(&mut self, accepted: bool, distance: f64) {
+        self.tries += 1;
+        if accepted {
+            self.acceptances += 1;
+        }
+        let deviation = distance - self.mean_distance;
+        self.mean_distance += deviation / self.tries as f64;
+        self.squared_deviations += deviation * (distance - self.mean_distance);
+    }
+
+    /// Pools `other` into these statistics; `iter` and `temperature` become
+    /// those of `other`.
+    pub fn merge(&mut self, other: &PlateauStats) {
+        let tries = self.tries + other.tries;
+        if tries > 0 {
+            let deviation = other.mean_distance - self.mean_distance;
+            let weight = self.tries as f64 * other.tries as f64 / tries as f64;
+            self.mean_distance += deviation * other.tries as f64 / tries as f64;
+            self.squared_deviations += other.squared_deviations + deviation * deviation * weight;
+        }
+        self.tries = tries;
+        self.acceptances += other.acceptances;
+        self.iter = other.iter;
+        self.temperature = other.temperature;
+    }
+
+    pub fn acceptance_ratio(&self) -> f64 {
+        self.acceptances as f64 / self.tries.max(1) as f64
+    }
+
+    pub fn distance_variance(&self) -> f64 {
+        self.squared_deviations / self.tries.max(1) as f64
+    }
+}
+
+/// A cooling method chosen at runtime, see [`Schedule`].
+pub type DynCooling = Box<dyn CoolingMethod + Send>;
+
+/// `create` builds the default [`Schedule`]; use [`Schedule::create`] for
+/// the others.
+impl CoolingMethod for DynCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        self.as_ref().get_next_temperature(current_iter)
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        Schedule::default().create(initial_temperature, final_temperature, qtd_iters)
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.as_mut()
+            .set_temperatures(initial_temperature, final_temperature);
+    }
+
+    fn next_temperature(&mut self, stats: &PlateauStats) -> f64 {
+        self.as_mut().next_temperature(stats)
+    }
+}
+
+/// The cooling methods by name, so a config file or a flag can pick one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Schedule {
+    #[default]
+    Exp,
+    Cos,
+    Sigmoid,
+    Linear,
+    Log,
+    LundyMees,
+    Quadratic,
+    Cauchy,
+    Tanh,
+    Adaptive,
+}
+
+impl Schedule {
+    pub const ALL: [Schedule; 10] = [
+        Schedule::Exp,
+        Schedule::Cos,
+        Schedule::Sigmoid,
+        Schedule::Linear,
+        Schedule::Log,
+        Schedule::LundyMees,
+        Schedule::Quadratic,
+        Schedule::Cauchy,
+        Schedule::Tanh,
+        Schedule::Adaptive,
+    ];
+
+    /// The schedule whose [`Display`] name is `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|schedule| schedule.to_string().eq_ignore_ascii_case(name))
+    }
+
+    pub fn create(
+        &self,
+        initial_temperature: f64,
+        final_temperature: f64,
+        qtd_iters: usize,
+    ) -> DynCooling {
+        let (t0, tf, n) = (initial_temperature, final_temperature, qtd_iters);
+        match self {
+            Schedule::Exp => Box::new(ExpCooling::create(t0, tf, n)),
+            Schedule::Cos => Box::new(CosCooling::create(t0, tf, n)),
+            Schedule::Sigmoid => Box::new(SigmoidCooling::create(t0, tf, n)),
+            Schedule::Linear => Box::new(LinearCooling::create(t0, tf, n)),
+            Schedule::Log => Box::new(LogCooling::create(t0, tf, n)),
+            Schedule::LundyMees => Box::new(LundyMeesCooling::create(t0, tf, n)),
+            Schedule::Quadratic => Box::new(QuadraticCooling::create(t0, tf, n)),
+            Schedule::Cauchy => Box::new(CauchyCooling::create(t0, tf, n)),
+            Schedule::Tanh => Box::new(TanhCooling::create(t0, tf, n)),
+            Schedule::Adaptive => Box::new(AdaptiveCooling::create(t0, tf, n)),
+        }
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Schedule::Exp => "exp",
+            Schedule::Cos => "cos",
+            Schedule::Sigmoid => "sigmoid",
+            Schedule::Linear => "linear",
+            Schedule::Log => "log",
+            Schedule::LundyMees => "lundy-mees",
+            Schedule::Quadratic => "quadratic",
+            Schedule::Cauchy => "cauchy",
+            Schedule::Tanh => "tanh",
+            Schedule::Adaptive => "adaptive",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 pub struct SigmoidCooling {
@@ -29,8 +204,7 @@ impl CoolingMethod for SigmoidCooling {
         let n = self.qtd_iters as f64;
         let a = delta_temp * (n + 1.0) / n;
         let b = self.initial_temperature - a;
-        let new_temp = a / (current_iter as f64 + 1.0) + b;
-        return new_temp;
+        a / (current_iter as f64 + 1.0) + b
     }
     fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
         SigmoidCooling {
@@ -39,14 +213,18 @@ impl CoolingMethod for SigmoidCooling {
             qtd_iters,
         }
     }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
 }
 
 impl CoolingMethod for ExpCooling {
     fn get_next_temperature(&self, current_iter: usize) -> f64 {
         let exp = current_iter as f64 / self.qtd_iters as f64;
         let fraction = self.final_temperature / self.initial_temperature;
-        let new_temp = self.initial_temperature * fraction.powf(exp);
-        return new_temp;
+        self.initial_temperature * fraction.powf(exp)
     }
 
     fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
@@ -56,6 +234,11 @@ impl CoolingMethod for ExpCooling {
             qtd_iters,
         }
     }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
 }
 
 impl CoolingMethod for CosCooling {
@@ -65,8 +248,7 @@ impl CoolingMethod for CosCooling {
         let pcos = i * PI / self.qtd_iters as f64;
         let p2 = 1.0 + pcos.cos();
         let p3 = self.final_temperature;
-        let new_temp = p1 * p2 + p3;
-        return new_temp;
+        p1 * p2 + p3
     }
 
     fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
@@ -76,4 +258,398 @@ impl CoolingMethod for CosCooling {
             qtd_iters,
         }
     }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Drops by the same amount every iteration.
+pub struct LinearCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for LinearCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        let progress = current_iter as f64 / self.qtd_iters as f64;
+        self.initial_temperature - (self.initial_temperature - self.final_temperature) * progress
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        LinearCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Geman and Geman's logarithmic schedule, `a / ln(i + e) + b`.
+pub struct LogCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for LogCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        // ln(0 + e) = 1, so a + b is the initial temperature.
+        let delta_temp = self.initial_temperature - self.final_temperature;
+        let end = (self.qtd_iters as f64 + E).ln();
+        let a = delta_temp * end / (end - 1.0);
+        let b = self.initial_temperature - a;
+        a / (current_iter as f64 + E).ln() + b
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        LogCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Lundy and Mees' `T' = T / (1 + beta * T)`, in closed form.
+pub struct LundyMeesCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for LundyMeesCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        // 1 / T grows by beta every iteration.
+        let beta =
+            (1.0 / self.final_temperature - 1.0 / self.initial_temperature) / self.qtd_iters as f64;
+        1.0 / (1.0 / self.initial_temperature + beta * current_iter as f64)
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        LundyMeesCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Additive quadratic: the gap to `final_temperature` shrinks with the
+/// square of the remaining iterations.
+pub struct QuadraticCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for QuadraticCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        let remaining = 1.0 - current_iter as f64 / self.qtd_iters as f64;
+        let delta_temp = self.initial_temperature - self.final_temperature;
+        self.final_temperature + delta_temp * remaining * remaining
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        QuadraticCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Szu and Hartley's fast annealing, `T0 / (1 + k)`, with `k` scaled so
+/// it ends at `final_temperature`.
+pub struct CauchyCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for CauchyCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        let k = current_iter as f64 / self.qtd_iters as f64
+            * (self.initial_temperature / self.final_temperature - 1.0);
+        self.initial_temperature / (1.0 + k)
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        CauchyCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// A true sigmoid (tanh, a rescaled logistic): flat at both ends, steepest
+/// halfway.
+pub struct TanhCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+}
+
+impl CoolingMethod for TanhCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        let step =
+            |iter: usize| (TANH_STEEPNESS * (iter as f64 / self.qtd_iters as f64 - 0.5)).tanh();
+        let (first, last) = (step(0), step(self.qtd_iters));
+        let delta_temp = self.initial_temperature - self.final_temperature;
+        self.final_temperature + delta_temp * (last - step(current_iter)) / (last - first)
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        TanhCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+}
+
+/// Holds `initial_temperature` for the whole run, as the replicas of
+/// [`crate::tempering::ParallelTempering`] do.
+pub struct ConstantCooling {
+    temperature: f64,
+}
+
+impl CoolingMethod for ConstantCooling {
+    fn get_next_temperature(&self, _current_iter: usize) -> f64 {
+        self.temperature
+    }
+
+    fn create(initial_temperature: f64, _final_temperature: f64, _qtd_iters: usize) -> Self {
+        ConstantCooling {
+            temperature: initial_temperature,
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, _final_temperature: f64) {
+        self.temperature = initial_temperature;
+    }
+}
+
+/// Aarts and van Laarhoven's schedule: each step is sized by the spread of
+/// the tour lengths seen at the current temperature, `T' = T / (1 + T
+/// ln(1 + delta) / (3 sigma))`, so it cools slowly where the cost varies a
+/// lot. It never goes below `final_temperature`; without statistics it
+/// follows [`ExpCooling`].
+pub struct AdaptiveCooling {
+    initial_temperature: f64,
+    final_temperature: f64,
+    qtd_iters: usize,
+    // Plateaus since the last step.
+    pooled: PlateauStats,
+}
+
+impl CoolingMethod for AdaptiveCooling {
+    fn get_next_temperature(&self, current_iter: usize) -> f64 {
+        let exp = current_iter as f64 / self.qtd_iters as f64;
+        let fraction = self.final_temperature / self.initial_temperature;
+        self.initial_temperature * fraction.powf(exp)
+    }
+
+    fn create(initial_temperature: f64, final_temperature: f64, qtd_iters: usize) -> Self {
+        AdaptiveCooling {
+            initial_temperature,
+            final_temperature,
+            qtd_iters,
+            pooled: PlateauStats::default(),
+        }
+    }
+
+    fn set_temperatures(&mut self, initial_temperature: f64, final_temperature: f64) {
+        self.initial_temperature = initial_temperature;
+        self.final_temperature = final_temperature;
+    }
+
+    fn next_temperature(&mut self, stats: &PlateauStats) -> f64 {
+        self.pooled.merge(stats);
+        if self.pooled.tries < ADAPTIVE_MIN_SAMPLES {
+            return stats.temperature;
+        }
+        let sigma = self.pooled.distance_variance().sqrt();
+        self.pooled = PlateauStats::default();
+        let temperature = stats.temperature;
+        // Nothing moved: the chain is frozen.
+        if sigma == 0.0 {
+            return self.final_temperature;
+        }
+        let next =
+            temperature / (1.0 + temperature * (1.0 + ADAPTIVE_DISTANCE).ln() / (3.0 * sigma));
+        next.max(self.final_temperature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cooling_methods::{
+        AdaptiveCooling, CauchyCooling, CoolingMethod, CosCooling, DynCooling, LinearCooling,
+        LogCooling, LundyMeesCooling, PlateauStats, QuadraticCooling, Schedule, TanhCooling,
+    };
+
+    // Starts at the initial temperature, ends at the final one and never
+    // heats up in between.
+    fn assert_endpoints<T: CoolingMethod>() {
+        for (initial, fin, qtd_iters) in [(10.0, 1.0, 7), (800.0, 1e-6, 3_000_000)] {
+            let cooling = T::create(initial, fin, qtd_iters);
+            let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * b.max(1.0);
+            assert!(close(cooling.get_next_temperature(0), initial));
+            assert!(close(cooling.get_next_temperature(qtd_iters), fin));
+            let mut last = f64::INFINITY;
+            for step in 0..=100 {
+                let temperature = cooling.get_next_temperature(qtd_iters * step / 100);
+                assert!(temperature <= last + 1e-9);
+                last = temperature;
+            }
+        }
+    }
+
+    #[test]
+    fn linear_hits_both_temperatures() {
+        assert_endpoints::<LinearCooling>();
+    }
+
+    #[test]
+    fn log_hits_both_temperatures() {
+        assert_endpoints::<LogCooling>();
+    }
+
+    #[test]
+    fn lundy_mees_hits_both_temperatures() {
+        assert_endpoints::<LundyMeesCooling>();
+    }
+
+    #[test]
+    fn quadratic_hits_both_temperatures() {
+        assert_endpoints::<QuadraticCooling>();
+    }
+
+    #[test]
+    fn cauchy_hits_both_temperatures() {
+        assert_endpoints::<CauchyCooling>();
+    }
+
+    #[test]
+    fn tanh_hits_both_temperatures() {
+        assert_endpoints::<TanhCooling>();
+    }
+
+    fn plateau(temperature: f64, distances: &[f64]) -> PlateauStats {
+        let mut stats = PlateauStats {
+            temperature,
+            ..PlateauStats::default()
+        };
+        for distance in distances {
+            stats.record(true, *distance);
+        }
+        stats
+    }
+
+    #[test]
+    fn plateau_stats_pool_exactly() {
+        let distances = (0..30).map(|i| (i * i % 17) as f64).collect::<Vec<_>>();
+        let mut pooled = plateau(1.0, &distances[..12]);
+        pooled.merge(&plateau(1.0, &distances[12..]));
+        let whole = plateau(1.0, &distances);
+        assert_eq!(pooled.tries, 30);
+        assert!((pooled.mean_distance - whole.mean_distance).abs() < 1e-9);
+        assert!((pooled.distance_variance() - whole.distance_variance()).abs() < 1e-9);
+        assert_eq!(whole.acceptance_ratio(), 1.0);
+    }
+
+    #[test]
+    fn adaptive_cools_slower_when_the_cost_varies() {
+        let steady = (0..1000)
+            .map(|i| 100.0 + (i % 2) as f64)
+            .collect::<Vec<_>>();
+        let varied = (0..1000)
+            .map(|i| 100.0 + (i % 2) as f64 * 50.0)
+            .collect::<Vec<_>>();
+        let step = |distances: &[f64]| {
+            AdaptiveCooling::create(10.0, 0.1, 1000).next_temperature(&plateau(10.0, distances))
+        };
+        assert!(step(&steady) < step(&varied));
+        assert!(step(&varied) < 10.0);
+        assert_eq!(step(&[100.0; 1000]), 0.1);
+
+        // Too few samples: the temperature is held until enough are pooled.
+        let mut cooling = AdaptiveCooling::create(10.0, 0.1, 1000);
+        assert_eq!(
+            cooling.next_temperature(&plateau(10.0, &varied[..500])),
+            10.0
+        );
+        assert!(cooling.next_temperature(&plateau(10.0, &varied[500..])) < 10.0);
+    }
+
+    #[test]
+    fn every_schedule_hits_both_temperatures() {
+        for schedule in Schedule::ALL {
+            let cooling = schedule.create(50.0, 0.5, 1000);
+            assert!(
+                (cooling.get_next_temperature(0) - 50.0).abs() < 1e-9,
+                "{}",
+                schedule
+            );
+            assert!(
+                (cooling.get_next_temperature(1000) - 0.5).abs() < 1e-9,
+                "{}",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn schedules_by_name() {
+        for schedule in Schedule::ALL {
+            assert_eq!(Schedule::from_name(&schedule.to_string()), Some(schedule));
+        }
+        assert_eq!(Schedule::from_name("COS"), Some(Schedule::Cos));
+        assert_eq!(Schedule::from_name("boltzmann"), None);
+
+        let boxed = Schedule::Cos.create(100.0, 1.0, 1000);
+        let direct = CosCooling::create(100.0, 1.0, 1000);
+        for iter in [0, 250, 500, 1000] {
+            assert_eq!(
+                boxed.get_next_temperature(iter),
+                direct.get_next_temperature(iter)
+            );
+        }
+        let default = DynCooling::create(100.0, 1.0, 1000);
+        assert!((default.get_next_temperature(1000) - 1.0).abs() < 1e-9);
+    }
 }
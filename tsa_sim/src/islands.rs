@@ -0,0 +1,263 @@
+use std::{sync::Mutex, thread};
+
+use data_retrieve::{error::LoadError, tour::Tour, tsplib::Instance, Data};
+
+use crate::{cooling_methods::CoolingMethod, stop::StopCondition, TSAConfig, TSA};
+
+// The best tour published so far.
+struct Incumbent {
+    solution: Vec<usize>,
+    distance: f64,
+}
+
+/// Multi-start annealing: one [`TSA`] chain per island, each on its own
+/// thread and all on one shared distance matrix. Every `migration_interval`
+/// iterations a chain publishes its best tour if it beats the shared one,
+/// or else moves to the shared one if another chain found a shorter tour.
+///
+/// Chains migrate whenever their threads get there, so unlike a single
+/// [`TSA`] the result is not fixed by the seeds.
+pub struct IslandModel<T>
+where
+    T: CoolingMethod + Send + 'static,
+{
+    chains: Vec<TSA<T>>,
+    migration_interval: usize,
+    // Per chain, times it moved to the shared tour.
+    adoptions: Vec<usize>,
+}
+
+impl<T: CoolingMethod + Send + 'static> IslandModel<T> {
+    /// `config` gives the config of island `i`, so seeds and schedules can
+    /// differ between chains. Panics without islands.
+    pub fn create<F>(data: Data, islands: usize, migration_interval: usize, config: F) -> Self
+    where
+        F: Fn(usize) -> TSAConfig<T>,
+    {
+        let first = TSA::create(data, config(0));
+        Self::create_islands(first, islands, migration_interval, config)
+    }
+
+    /// Fails like [`TSA::from_instance`]. Panics without islands.
+    pub fn from_instance<F>(
+        instance: Instance,
+        islands: usize,
+        migration_interval: usize,
+        config: F,
+    ) -> Result<Self, LoadError>
+    where
+        F: Fn(usize) -> TSAConfig<T>,
+    {
+        let first = TSA::from_instance(instance, config(0))?;
+        Ok(Self::create_islands(
+            first,
+            islands,
+            migration_interval,
+            config,
+        ))
+    }
+
+    /// Panics without islands.
+    pub fn create_from_matrix<F>(
+        distances: Vec<Vec<f64>>,
+        islands: usize,
+        migration_interval: usize,
+        config: F,
+    ) -> Self
+    where
+        F: Fn(usize) -> TSAConfig<T>,
+    {
+        let first = TSA::create_from_matrix(distances, config(0));
+        Self::create_islands(first, islands, migration_interval, config)
+    }
+
+    // The other chains share the matrix of `first`.
+    fn create_islands<F>(
+        first: TSA<T>,
+        islands: usize,
+        migration_interval: usize,
+        config: F,
+    ) -> Self
+    where
+        F: Fn(usize) -> TSAConfig<T>,
+    {
+        assert!(islands > 0, "An island model needs at least one island");
+        let mut chains = Vec::with_capacity(islands);
+        for island in 1..islands {
+            chains.push(TSA::create_shared(
+                first.data.clone(),
+                first.distances.clone(),
+                config(island),
+            ));
+        }
+        chains.insert(0, first);
+        Self {
+            chains,
+            migration_interval: migration_interval.max(1),
+            adoptions: vec![0; islands],
+        }
+    }
+
+    /// Runs every chain until it stops, and returns why each one did.
+    pub fn run(&mut self) -> Vec<StopCondition> {
+        let best = self.best_chain();
+        let incumbent = Mutex::new(Incumbent {
+            solution: best.get_best_solution().to_vec(),
+            distance: best.get_best_distance(),
+        });
+        let interval = self.migration_interval;
+        thread::scope(|scope| {
+            let handles = self
+                .chains
+                .iter_mut()
+                .zip(self.adoptions.iter_mut())
+                .map(|(chain, adoptions)| {
+                    let incumbent = &incumbent;
+                    scope.spawn(move || loop {
+                        if let Some(reason) = chain.get_stop_reason() {
+                            // A stopped chain only hands its best tour on.
+                            publish(chain, &mut incumbent.lock().unwrap());
+                            return reason;
+                        }
+                        chain.gen_next_solution();
+                        if chain.get_current_iter() % interval == 0 && migrate(chain, incumbent) {
+                            *adoptions += 1;
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+
+    pub fn get_chains(&self) -> &[TSA<T>] {
+        &self.chains
+    }
+
+    /// Times each chain moved to the shared best tour.
+    pub fn get_adoptions(&self) -> &[usize] {
+        &self.adoptions
+    }
+
+    fn best_chain(&self) -> &TSA<T> {
+        self.chains
+            .iter()
+            .min_by(|a, b| a.get_best_distance().total_cmp(&b.get_best_distance()))
+            .unwrap()
+    }
+
+    /// Best tour found by any chain.
+    pub fn get_best_solution(&self) -> &[usize] {
+        self.best_chain().get_best_solution()
+    }
+
+    pub fn get_best_distance(&self) -> f64 {
+        self.best_chain().get_best_distance()
+    }
+
+    pub fn get_best_tour(&self, name: &str) -> Tour {
+        self.best_chain().get_best_tour(name)
+    }
+}
+
+// Makes the chain's best tour the shared one if it is shorter. Returns
+// whether it was.
+fn publish<T: CoolingMethod + 'static>(chain: &TSA<T>, incumbent: &mut Incumbent) -> bool {
+    if chain.get_best_distance() < incumbent.distance {
+        incumbent.solution = chain.get_best_solution().to_vec();
+        incumbent.distance = chain.get_best_distance();
+        true
+    } else {
+        false
+    }
+}
+
+// Publishes the chain's best tour, or moves the chain to the shared one.
+// Returns whether the chain moved.
+fn migrate<T: CoolingMethod + 'static>(chain: &mut TSA<T>, incumbent: &Mutex<Incumbent>) -> bool {
+    let mut incumbent = incumbent.lock().unwrap();
+    if publish(chain, &mut incumbent) || incumbent.distance >= chain.get_best_distance() {
+        return false;
+    }
+    chain.adopt_solution(&incumbent.solution);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{
+        cooling_methods::ExpCooling,
+        islands::{migrate, Incumbent, IslandModel},
+        moves::Operator,
+        TSAConfig, TSA,
+    };
+
+    #[test]
+    fn islands_share_the_matrix_and_the_best_tour() {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let mut islands = IslandModel::create(data, 4, 1000, |island| {
+            TSAConfig::<ExpCooling>::create(1.0, 50.0, 30000, 10)
+                .with_operators(vec![(Operator::TwoOpt, 1.0), (Operator::OrOpt, 1.0)])
+                .with_seed(20 + island as u64)
+        });
+        let reasons = islands.run();
+        assert_eq!(reasons.len(), 4);
+
+        let chains = islands.get_chains();
+        let best = islands.get_best_distance();
+        for chain in chains {
+            assert!(Arc::ptr_eq(&chain.distances, &chains[0].distances));
+            assert_eq!(chain.get_current_iter(), 30000);
+            assert!(best <= chain.get_best_distance());
+            assert_eq!(
+                chain.get_current_distance(),
+                chain.get_solution_distance(&chain.solution)
+            );
+        }
+
+        let mut sorted = islands.get_best_solution().to_vec();
+        sorted.sort();
+        assert_eq!(sorted, (0..51).collect::<Vec<_>>());
+        assert_eq!(
+            chains[0].get_solution_distance(islands.get_best_solution()),
+            best
+        );
+        // eil51's optimum is 426.
+        assert!(best < 426.0 * 1.1, "{}", best);
+    }
+
+    #[test]
+    fn chains_publish_shorter_tours_and_adopt_the_shared_one() {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let config = |seed| {
+            TSAConfig::<ExpCooling>::create(1.0, 50.0, 20000, 10)
+                .with_operators(vec![(Operator::TwoOpt, 1.0)])
+                .with_seed(seed)
+        };
+        let mut annealed = TSA::create(data.clone(), config(1));
+        annealed.run();
+        let mut fresh = TSA::create(data, config(2));
+        assert!(annealed.get_best_distance() < fresh.get_best_distance());
+
+        let incumbent = Mutex::new(Incumbent {
+            solution: vec![],
+            distance: f64::INFINITY,
+        });
+        // The annealed chain publishes and keeps its own tour.
+        assert!(!migrate(&mut annealed, &incumbent));
+        assert_eq!(
+            incumbent.lock().unwrap().distance,
+            annealed.get_best_distance()
+        );
+        // The fresh one moves to it, and then has nothing to adopt.
+        assert!(migrate(&mut fresh, &incumbent));
+        assert_eq!(fresh.get_best_distance(), annealed.get_best_distance());
+        assert_eq!(fresh.solution, annealed.get_best_solution());
+        assert!(!migrate(&mut fresh, &incumbent));
+    }
+}
@@ -1,8 +1,33 @@
+pub mod calibration;
+pub mod christofides;
+pub mod construction;
 pub mod cooling_methods;
+pub mod islands;
+mod matching;
+pub mod metric;
+pub mod moves;
+pub mod reheat;
+pub mod selection;
+pub mod stop;
+pub mod tempering;
 
-use cooling_methods::{CoolingMethod, SigmoidCooling};
-use data_retrieve::{Data, DataNode, Point};
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use calibration::Calibration;
+use construction::Construction;
+use cooling_methods::{CoolingMethod, DynCooling, PlateauStats, Schedule, SigmoidCooling};
+use data_retrieve::{
+    best_known::BestKnown,
+    error::{LoadError, LoadErrorKind},
+    tour::Tour,
+    tsplib::Instance,
+    Data, DataNode, NodeIndex,
+};
+use metric::Metric;
+use moves::{Move, Neighborhood, Operator, MIN_TOUR_SIZE};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use reheat::{Reheat, ReheatEvent};
+use selection::{OperatorSelection, OperatorSelector, OperatorStats};
+use std::{sync::Arc, time::Instant};
+use stop::{Progress, StopCondition};
 
 pub struct TSAConfig<T>
 where
@@ -13,28 +38,84 @@ where
     pub qtd_iters: usize,
     pub qtd_iters_on_temp: usize,
     pub cooling_method: T,
+    /// Neighborhoods to draw moves from, with their relative probabilities.
+    pub operators: Vec<(Operator, f64)>,
+    pub operator_selection: OperatorSelection,
+    /// Seeds every random decision of the run; the same seed and config
+    /// give the same trajectory.
+    pub seed: u64,
+    /// How the starting tour is built.
+    pub construction: Construction,
+    /// When set, `initial_temperature` and `final_temperature` are derived
+    /// from the instance when the run is created.
+    pub calibration: Option<Calibration>,
+    /// Ends the run before `qtd_iters` when met.
+    pub stop: Option<StopCondition>,
+    pub reheat: Option<Reheat>,
 }
 
 pub struct TSA<T>
 where
     T: CoolingMethod + 'static,
 {
-    pub distances: Vec<Vec<f64>>,
+    /// Read-only, so runs on the same instance can share it.
+    pub distances: Arc<Vec<Vec<f64>>>,
     pub data: Vec<DataNode>,
+    pub ids: NodeIndex,
     pub solution: Vec<usize>,
     current_distance: f64,
+    best_solution: Vec<usize>,
+    best_distance: f64,
+    best_iter: usize,
     temperature: f64,
+    // What the reheats add to the schedule's temperature, as a factor. It
+    // fades from `reheat_peak` right after the last reheat back to 1.0 at
+    // `qtd_iters`, so the run still ends at the final temperature.
+    temperature_scale: f64,
+    reheat_peak: f64,
+    reheats: Vec<ReheatEvent>,
     iters_on_temp: usize,
     current_iter: usize,
+    plateau: PlateauStats,
+    started: Instant,
+    operators: OperatorSelector,
+    rng: StdRng,
     config: TSAConfig<T>,
 }
 
-fn dist(a: &Point, b: &Point) -> f64 {
-    let x_diff: f64 = b.x as f64 - a.x as f64;
-    let y_diff: f64 = b.y as f64 - a.y as f64;
-    let sum = x_diff * x_diff + y_diff * y_diff;
+/// How far `distance` is above `best_known`, in percent.
+pub fn gap(distance: f64, best_known: f64) -> f64 {
+    (distance - best_known) / best_known * 100.0
+}
 
-    sum.sqrt()
+/// The matrix [`TSA::from_instance`] would build for `instance`, so it can
+/// be built once and shared between runs through [`TSA::create_shared`].
+pub fn distance_matrix(instance: &Instance) -> Result<Vec<Vec<f64>>, LoadError> {
+    if let Some(distances) = &instance.edge_weights {
+        return Ok(distances.clone());
+    }
+    Ok(metric_matrix(&instance.data, instance_metric(instance)?))
+}
+
+fn instance_metric(instance: &Instance) -> Result<Metric, LoadError> {
+    match instance.edge_weight_type {
+        Some(edge_weight_type) => Metric::from_edge_weight_type(edge_weight_type).ok_or(
+            LoadError::new(LoadErrorKind::UnsupportedEdgeWeightType(edge_weight_type)),
+        ),
+        None => Ok(Metric::default()),
+    }
+}
+
+fn metric_matrix(data: &Data, metric: Metric) -> Vec<Vec<f64>> {
+    let mut matrix = vec![vec![0.0; data.len()]; data.len()];
+    for (index_1, item_1) in data.iter().enumerate() {
+        for (index_2, item_2) in data.iter().enumerate().skip(index_1 + 1) {
+            let distance = metric.distance(&item_1.point, &item_2.point);
+            matrix[index_1][index_2] = distance;
+            matrix[index_2][index_1] = distance;
+        }
+    }
+    matrix
 }
 
 impl<T: CoolingMethod + 'static> TSA<T> {
@@ -46,11 +127,11 @@ impl<T: CoolingMethod + 'static> TSA<T> {
         self.temperature
     }
 
-    pub fn get_solution_distance(&self, solution: &Vec<usize>) -> f64 {
-        return Self::_get_solution_distance(&self.distances, solution);
+    pub fn get_solution_distance(&self, solution: &[usize]) -> f64 {
+        Self::_get_solution_distance(&self.distances, solution)
     }
 
-    fn _get_solution_distance(distances: &Vec<Vec<f64>>, solution: &Vec<usize>) -> f64 {
+    fn _get_solution_distance(distances: &[Vec<f64>], solution: &[usize]) -> f64 {
         let mut dist = 0.0;
         let size = solution.len();
         for origem_index in 0..size {
@@ -58,7 +139,35 @@ impl<T: CoolingMethod + 'static> TSA<T> {
             let destiny = solution[(origem_index + 1) % size]; // mod size to wrap to the first item
             dist += distances[origem][destiny];
         }
-        return dist;
+        dist
+    }
+
+    /// Runs until a stop condition is met, or the schedule's `qtd_iters`
+    /// are done, and returns the condition that ended it.
+    pub fn run(&mut self) -> StopCondition {
+        loop {
+            if let Some(reason) = self.get_stop_reason() {
+                return reason;
+            }
+            self.gen_next_solution();
+        }
+    }
+
+    /// Why the run is over, or `None` while it is not. Running out of
+    /// `qtd_iters` is reported as [`StopCondition::Iterations`].
+    pub fn get_stop_reason(&self) -> Option<StopCondition> {
+        let progress = Progress {
+            iter: self.current_iter,
+            elapsed: self.started.elapsed(),
+            temperature: self.temperature,
+            best_distance: self.best_distance,
+            best_iter: self.best_iter,
+        };
+        self.config
+            .stop
+            .as_ref()
+            .and_then(|stop| stop.met(&progress))
+            .or_else(|| StopCondition::Iterations(self.config.qtd_iters).met(&progress))
     }
 
     pub fn gen_next_solution(&mut self) {
@@ -70,93 +179,113 @@ impl<T: CoolingMethod + 'static> TSA<T> {
         // dbg!(self.temperature);
         // dbg!(self.current_distance);
 
-        let mut rng = thread_rng();
-        let qtd = rng.gen_range(1usize..=5);
-        // println!("{}", qtd);
-        let initial_size = self.solution.len();
-        let new_solution = TSA::<T>::permute(&self.solution, qtd);
-        assert_eq!(initial_size, self.solution.len());
-        assert_eq!(initial_size, new_solution.len());
+        // Every tour of fewer nodes has the same length.
+        if self.solution.len() < MIN_TOUR_SIZE {
+            self.update_temperature();
+            return;
+        }
 
-        let new_distance = self.get_solution_distance(&new_solution);
+        let operator_index = self.operators.pick(&mut self.rng);
+        let candidate = self
+            .operators
+            .operator(operator_index)
+            .random_move(&self.solution, &mut self.rng);
+        let delta = candidate.delta(&self.distances, &self.solution);
 
-        if new_distance < self.current_distance || self.should_change(new_distance) {
-            self.current_distance = new_distance;
-            self.solution = new_solution;
+        let accepted = delta < 0.0 || self.should_change(delta);
+        if accepted {
+            candidate.apply(&mut self.solution);
+            self.current_distance += delta;
+            if self.current_distance < self.best_distance {
+                self.update_best(self.current_iter + 1);
+            }
             // return;
         }
+        self.operators.record(operator_index, accepted, delta < 0.0);
+        self.plateau.record(accepted, self.current_distance);
 
         // println!("{} {} {}", self.current_iter, self.current_distance, self.temperature);
         self.update_temperature();
+        self.reheat_if_stagnant();
         // let itera = self.current_iter * self.config.qtd_iters_on_temp + self.iters_on_temp;
     }
 
-    fn should_change(&self, new_distance: f64) -> bool {
+    fn update_best(&mut self, iter: usize) {
+        self.best_solution.clone_from(&self.solution);
+        self.best_distance = self.current_distance;
+        self.best_iter = iter;
+    }
+
+    fn should_change(&mut self, delta: f64) -> bool {
         if self.temperature <= self.config.final_temperature {
             return false;
         }
-        let mut rng = thread_rng();
-        let value = rng.gen_range(0.0..=1.0);
+        let value = self.rng.gen_range(0.0..=1.0);
         let e = std::f64::consts::E;
-        let delta = new_distance - self.current_distance;
         let prob = e.powf(-delta / self.temperature);
         // println!("-------------------------------------");
         // println!("prob {}", prob);
         // println!("distance {}", delta);
         // println!("temp {}", self.temperature);
         // println!("-------------------------------------");
-        assert!(0.0 <= prob && prob <= 1.0);
-        return value <= prob;
+        assert!((0.0..=1.0).contains(&prob));
+        value <= prob
     }
 
     fn update_temperature(&mut self) {
         self.iters_on_temp += 1;
         self.current_iter += 1;
-        if self.iters_on_temp % self.config.qtd_iters_on_temp != 0 {
+        if !self
+            .iters_on_temp
+            .is_multiple_of(self.config.qtd_iters_on_temp)
+        {
             return;
         }
         self.iters_on_temp = 0;
+        self.operators.end_step();
 
         // self.cooling_method
 
-        self.temperature = self
-            .config
-            .cooling_method
-            .get_next_temperature(self.current_iter);
+        let mut plateau = std::mem::take(&mut self.plateau);
+        plateau.iter = self.current_iter;
+        // The schedule sees its own temperature, before any reheat.
+        plateau.temperature = self.temperature / self.temperature_scale;
+        if let Some(event) = self.reheats.last() {
+            let remaining = self.config.qtd_iters.saturating_sub(self.current_iter);
+            let span = self.config.qtd_iters.saturating_sub(event.iter).max(1);
+            self.temperature_scale = self.reheat_peak.powf(remaining as f64 / span as f64);
+        }
+        self.temperature =
+            self.config.cooling_method.next_temperature(&plateau) * self.temperature_scale;
     }
 
-    fn permute(solution: &Vec<usize>, qtd: usize) -> Vec<usize> {
-        let size = solution.len();
-        let mut rng = thread_rng();
-        let mut new_solution = solution.clone();
-
-        for i in 0..solution.len() {
-            assert_eq!(solution[i], new_solution[i]);
+    fn reheat_if_stagnant(&mut self) {
+        let Some(reheat) = self.config.reheat else {
+            return;
+        };
+        if self.reheats.len() >= reheat.max_reheats {
+            return;
         }
-
-        for _ in 0..qtd {
-            let mut index_1: usize;
-            let mut index_2: usize;
-            loop {
-                index_1 = rng.gen_range(0..size);
-                index_2 = rng.gen_range(0..size);
-                if index_1 != index_2 {
-                    break;
-                }
-            }
-
-            let value_1 = new_solution[index_1];
-            let value_2 = new_solution[index_2];
-
-            new_solution[index_1] = value_2;
-            new_solution[index_2] = value_1;
-
-            assert_eq!(value_1, new_solution[index_2]);
-            assert_eq!(value_2, new_solution[index_1]);
-            assert_ne!(value_1, value_2);
-            assert_ne!(new_solution[index_1], new_solution[index_2]);
+        let last_change = self
+            .reheats
+            .last()
+            .map_or(0, |event| event.iter)
+            .max(self.best_iter);
+        if self.current_iter - last_change < reheat.stagnation {
+            return;
         }
-        return new_solution;
+        self.temperature_scale *= reheat.factor;
+        self.reheat_peak = self.temperature_scale;
+        self.temperature *= reheat.factor;
+        if reheat.restart_from_best {
+            self.solution.clone_from(&self.best_solution);
+            self.current_distance = self.best_distance;
+        }
+        self.reheats.push(ReheatEvent {
+            iter: self.current_iter,
+            temperature: self.temperature,
+            restarted: reheat.restart_from_best,
+        });
     }
 }
 
@@ -175,6 +304,19 @@ impl TSAConfig<SigmoidCooling> {
     }
 }
 
+impl TSAConfig<DynCooling> {
+    /// Swaps the cooling method for `schedule`, over the same temperatures
+    /// and iterations.
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.cooling_method = schedule.create(
+            self.initial_temperature,
+            self.final_temperature,
+            self.qtd_iters,
+        );
+        self
+    }
+}
+
 impl<T: CoolingMethod + 'static> TSAConfig<T> {
     pub fn create(
         final_temperature: f64,
@@ -188,64 +330,782 @@ impl<T: CoolingMethod + 'static> TSAConfig<T> {
             qtd_iters,
             qtd_iters_on_temp,
             cooling_method: T::create(initial_temperature, final_temperature, qtd_iters),
+            operators: vec![(Operator::KSwap, 1.0)],
+            operator_selection: OperatorSelection::Fixed,
+            seed: thread_rng().gen(),
+            construction: Construction::Random,
+            calibration: None,
+            stop: None,
+            reheat: None,
         }
     }
+
+    pub fn with_construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    /// Replaces the seed drawn at random by `create`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Replaces the default k-swap neighborhood with `operators`, each drawn
+    /// with probability proportional to its weight.
+    pub fn with_operators(mut self, operators: Vec<(Operator, f64)>) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    pub fn with_reheat(mut self, reheat: Reheat) -> Self {
+        self.reheat = Some(reheat);
+        self
+    }
+
+    pub fn with_stop(mut self, stop: StopCondition) -> Self {
+        self.stop = Some(stop);
+        self
+    }
+
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = Some(calibration);
+        self
+    }
+
+    pub fn with_operator_selection(mut self, operator_selection: OperatorSelection) -> Self {
+        self.operator_selection = operator_selection;
+        self
+    }
 }
 
 impl<T: CoolingMethod + 'static> TSA<T> {
     pub fn create(data: Data, config: TSAConfig<T>) -> Self {
-        let distances = Self::euclidian_distance_matrix(&data);
-        let initial_solution = Self::get_initial_solution(data.len());
+        Self::create_with_metric(data, Metric::default(), config)
+    }
+
+    /// Fails on edge weight types without a [`Metric`], such as `EUC_3D`
+    /// or `EXPLICIT` without an `EDGE_WEIGHT_SECTION`.
+    pub fn from_instance(instance: Instance, config: TSAConfig<T>) -> Result<Self, LoadError> {
+        if let Some(distances) = instance.edge_weights {
+            return Ok(Self::create_with_distances(
+                instance.data,
+                Arc::new(distances),
+                config,
+            ));
+        }
+        let metric = instance_metric(&instance)?;
+        Ok(Self::create_with_metric(instance.data, metric, config))
+    }
+
+    pub fn create_with_metric(data: Data, metric: Metric, config: TSAConfig<T>) -> Self {
+        let distances = metric_matrix(&data, metric);
+        Self::create_with_distances(data, Arc::new(distances), config)
+    }
+
+    /// Builds the simulation straight from a distance matrix, for instances
+    /// without coordinates. `data` is left empty.
+    pub fn create_from_matrix(distances: Vec<Vec<f64>>, config: TSAConfig<T>) -> Self {
+        Self::create_with_distances(vec![], Arc::new(distances), config)
+    }
+
+    /// Builds the simulation on the matrix of another run, such as
+    /// `other.distances.clone()`, without copying it. `data` holds the
+    /// coordinates in the same order, or is empty.
+    pub fn create_shared(data: Data, distances: Arc<Vec<Vec<f64>>>, config: TSAConfig<T>) -> Self {
+        Self::create_with_distances(data, distances, config)
+    }
+
+    fn create_with_distances(
+        data: Data,
+        distances: Arc<Vec<Vec<f64>>>,
+        mut config: TSAConfig<T>,
+    ) -> Self {
+        let ids = if data.is_empty() {
+            NodeIndex::sequential(distances.len())
+        } else {
+            NodeIndex::from_data(&data)
+        };
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let initial_solution = config.construction.build(&distances, &data, &mut rng);
         let current_distance = Self::_get_solution_distance(&distances, &initial_solution);
+        let operators = OperatorSelector::create(&config.operators, config.operator_selection);
+        if let Some(calibration) = config.calibration {
+            Self::calibrate(
+                &mut config,
+                calibration,
+                &distances,
+                &initial_solution,
+                &operators,
+                &mut rng,
+            );
+        }
         let initial_temperature = config.initial_temperature;
         Self {
             distances,
             data,
+            ids,
+            best_solution: initial_solution.clone(),
+            best_distance: current_distance,
+            best_iter: 0,
             solution: initial_solution,
             current_distance,
             temperature: initial_temperature,
+            temperature_scale: 1.0,
+            reheat_peak: 1.0,
+            reheats: vec![],
             iters_on_temp: 0,
             current_iter: 0,
+            plateau: PlateauStats::default(),
+            started: Instant::now(),
+            operators,
+            rng,
             config,
         }
     }
 }
 
 impl<T: CoolingMethod + 'static> TSA<T> {
-    fn get_initial_solution(len: usize) -> Vec<usize> {
-        let mut solution = (0..len).collect::<Vec<usize>>();
-        solution.shuffle(&mut thread_rng());
-        return solution;
-    }
-
-    fn euclidian_distance_matrix(data: &Data) -> Vec<Vec<f64>> {
-        let mut matrix = vec![vec![0.0; data.len()]; data.len()];
-        for item_1 in data {
-            for item_2 in data {
-                let index_1 = item_1.group as usize - 1;
-                let index_2 = item_2.group as usize - 1;
-                let distance = dist(&item_1.point, &item_2.point);
-                matrix[index_1][index_2] = distance;
-                matrix[index_2][index_1] = distance;
-            }
+    // Prices moves drawn from `solution` without applying them, and sets the
+    // config's temperatures from them.
+    fn calibrate(
+        config: &mut TSAConfig<T>,
+        calibration: Calibration,
+        distances: &[Vec<f64>],
+        solution: &[usize],
+        operators: &OperatorSelector,
+        rng: &mut StdRng,
+    ) {
+        if solution.len() < MIN_TOUR_SIZE {
+            return;
+        }
+        let deltas = (0..calibration.samples)
+            .map(|_| {
+                let operator = operators.operator(operators.pick(rng));
+                operator
+                    .random_move(solution, rng)
+                    .delta(distances, solution)
+            })
+            .collect::<Vec<_>>();
+        if let Some((initial, fin)) = calibration.temperatures(&deltas) {
+            config.initial_temperature = initial;
+            config.final_temperature = fin;
+            config.cooling_method.set_temperatures(initial, fin);
         }
-        return matrix;
     }
 
     pub fn get_current_iter(&self) -> usize {
         self.current_iter
     }
+
+    pub fn get_reheats(&self) -> &[ReheatEvent] {
+        &self.reheats
+    }
+
+    /// The temperatures the run goes between, after any calibration.
+    pub fn get_temperature_range(&self) -> (f64, f64) {
+        (
+            self.config.initial_temperature,
+            self.config.final_temperature,
+        )
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.config.seed
+    }
+
+    /// Tries, acceptances and improvements of each operator so far.
+    pub fn get_operator_stats(&self) -> Vec<(Operator, OperatorStats)> {
+        self.operators.stats()
+    }
+
+    /// Current probability of each operator being picked.
+    pub fn get_operator_shares(&self) -> Vec<(Operator, f64)> {
+        self.operators.shares()
+    }
+
+    pub fn get_gap(&self, best_known: f64) -> f64 {
+        gap(self.current_distance, best_known)
+    }
+
+    /// Length of the best-known solution on this instance, evaluating its
+    /// tour against `distances` when no length was recorded.
+    pub fn get_best_known_distance(&self, best_known: &BestKnown) -> Option<f64> {
+        if let Some(distance) = best_known.distance {
+            return Some(distance);
+        }
+        let tour = best_known.tour.as_ref()?;
+        let solution = self.tour_indices(tour)?;
+        Some(self.get_solution_distance(&solution))
+    }
+
+    // Dense indices for a tour given by node ids, if it visits every node
+    // exactly once.
+    fn tour_indices(&self, ids: &[usize]) -> Option<Vec<usize>> {
+        if ids.len() != self.distances.len() {
+            return None;
+        }
+        let solution = self.ids.to_indices(ids)?;
+        let mut visited = vec![false; solution.len()];
+        for index in solution.iter() {
+            if visited[*index] {
+                return None;
+            }
+            visited[*index] = true;
+        }
+        Some(solution)
+    }
+
+    /// Length of `tour` under this instance's distances, or `None` if it is
+    /// not a tour over exactly these nodes.
+    pub fn evaluate_tour(&self, tour: &Tour) -> Option<f64> {
+        let solution = self.tour_indices(&tour.nodes)?;
+        Some(self.get_solution_distance(&solution))
+    }
+
+    /// Continues the annealing from `tour`, returning its length.
+    pub fn seed_from_tour(&mut self, tour: &Tour) -> Option<f64> {
+        let solution = self.tour_indices(&tour.nodes)?;
+        self.current_distance = self.get_solution_distance(&solution);
+        self.solution = solution;
+        if self.current_distance < self.best_distance {
+            self.update_best(self.current_iter);
+        }
+        Some(self.current_distance)
+    }
+
+    /// Moves the chain to `solution`, a tour of the same instance, such as the
+    /// best tour of another run.
+    pub fn adopt_solution(&mut self, solution: &[usize]) {
+        self.current_distance = self.get_solution_distance(solution);
+        self.solution = solution.to_vec();
+        if self.current_distance < self.best_distance {
+            self.update_best(self.current_iter);
+        }
+    }
+
+    /// Swaps the current tours of two runs on the same instance, as in a
+    /// replica exchange; each keeps its own temperature and schedule.
+    pub fn exchange_solutions(&mut self, other: &mut Self) {
+        std::mem::swap(&mut self.solution, &mut other.solution);
+        std::mem::swap(&mut self.current_distance, &mut other.current_distance);
+        for tsa in [self, other] {
+            if tsa.current_distance < tsa.best_distance {
+                tsa.update_best(tsa.current_iter);
+            }
+        }
+    }
+
+    pub fn get_tour(&self, name: &str) -> Tour {
+        let mut tour = Tour::create(name, self.get_solution_ids());
+        tour.comment = format!("Length {}", self.current_distance);
+        tour
+    }
+
+    /// The current tour written with the ids from the instance file.
+    pub fn get_solution_ids(&self) -> Vec<usize> {
+        self.ids.to_ids(&self.solution)
+    }
+
+    /// The shortest tour the chain has visited, as dense indices.
+    pub fn get_best_solution(&self) -> &[usize] {
+        &self.best_solution
+    }
+
+    pub fn get_best_distance(&self) -> f64 {
+        self.best_distance
+    }
+
+    /// Iterations run when the best tour was found, 0 for the initial tour.
+    pub fn get_best_iter(&self) -> usize {
+        self.best_iter
+    }
+
+    pub fn get_best_gap(&self, best_known: f64) -> f64 {
+        gap(self.best_distance, best_known)
+    }
+
+    pub fn get_best_tour(&self, name: &str) -> Tour {
+        let mut tour = Tour::create(name, self.ids.to_ids(&self.best_solution));
+        tour.comment = format!("Length {}", self.best_distance);
+        tour
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{TSAConfig, TSA};
+    use data_retrieve::{DataNode, Point};
+
+    use data_retrieve::{
+        best_known::{BestKnown, BestKnownRegistry},
+        error::LoadErrorKind,
+        generator::Generator,
+        tour::{write_tour, Tour},
+        tsplib::EdgeWeightType,
+    };
+
+    use std::time::Duration;
+
+    use crate::{
+        calibration::Calibration,
+        cooling_methods::{CoolingMethod, CosCooling, DynCooling, ExpCooling, Schedule},
+        gap,
+        moves::Operator,
+        reheat::Reheat,
+        selection::OperatorSelection,
+        stop::StopCondition,
+        TSAConfig, TSA,
+    };
 
     #[test]
     fn can_create_with_51_items_as_default() {
-        let data = data_retrieve::load("../data/inst_51.txt");
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
         let config = TSAConfig::create_default();
         let tsa = TSA::create(data, config);
         assert_eq!(tsa.distances.len(), 51);
     }
+
+    #[test]
+    fn raw_51_uses_tsplib_rounding() {
+        let instance = data_retrieve::tsplib::load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let config = TSAConfig::create_default();
+        let tsa = TSA::from_instance(instance, config).unwrap();
+        assert_eq!(tsa.distances[0][1], 12.0);
+        assert_eq!(tsa.distances[0][0], 0.0);
+        assert!(tsa.distances.iter().flatten().all(|d| d.fract() == 0.0));
+    }
+
+    #[test]
+    fn unsupported_edge_weight_types_are_errors() {
+        let sections = [
+            "EDGE_WEIGHT_TYPE: EUC_3D\nNODE_COORD_SECTION\n1 0 0 0\n2 3 4 0\n3 6 8 0\n",
+            "EDGE_WEIGHT_TYPE: SPECIAL\nNODE_COORD_SECTION\n1 0 0\n2 3 4\n3 6 8\n",
+        ];
+        let mut instances = sections
+            .iter()
+            .map(|section| {
+                let content = format!("NAME: bad\nDIMENSION: 3\n{}EOF\n", section);
+                data_retrieve::tsplib::parse_tsplib(&content).unwrap()
+            })
+            .collect::<Vec<_>>();
+        // The reader rejects EXPLICIT without weights, but an instance can
+        // also be built in code.
+        let mut explicit = instances[1].clone();
+        explicit.edge_weight_type = Some(EdgeWeightType::Explicit);
+        instances.push(explicit);
+        for instance in instances {
+            let edge_weight_type = instance.edge_weight_type.unwrap();
+            let err = TSA::from_instance(instance, TSAConfig::create_default())
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err.kind,
+                LoadErrorKind::UnsupportedEdgeWeightType(found) if found == edge_weight_type
+            ));
+        }
+    }
+
+    #[test]
+    fn can_create_from_matrix_alone() {
+        let distances = vec![
+            vec![0.0, 1.0, 2.0],
+            vec![1.0, 0.0, 3.0],
+            vec![2.0, 3.0, 0.0],
+        ];
+        let config = TSAConfig::create_default();
+        let tsa = TSA::create_from_matrix(distances, config);
+        assert!(tsa.data.is_empty());
+        assert_eq!(tsa.solution.len(), 3);
+        assert_eq!(tsa.get_current_distance(), 6.0);
+    }
+
+    #[test]
+    fn sparse_ids_over_255_nodes() {
+        let data = (0..300)
+            .map(|index| DataNode {
+                group: 1000 + 3 * index,
+                point: Point {
+                    x: index as f64,
+                    y: 0.0,
+                },
+            })
+            .collect::<Vec<_>>();
+        let config = TSAConfig::create_default();
+        let tsa = TSA::create(data, config);
+        assert_eq!(tsa.distances.len(), 300);
+        assert_eq!(tsa.distances[0][299], 299.0);
+
+        let mut ids = tsa.get_solution_ids();
+        ids.sort();
+        assert_eq!(ids.first(), Some(&1000));
+        assert_eq!(ids.last(), Some(&1897));
+    }
+
+    #[test]
+    fn gap_to_best_known() {
+        assert!((gap(468.6, 426.0) - 10.0).abs() < 1e-9);
+        let distances = vec![
+            vec![0.0, 1.0, 2.0, 1.0],
+            vec![1.0, 0.0, 1.0, 2.0],
+            vec![2.0, 1.0, 0.0, 1.0],
+            vec![1.0, 2.0, 1.0, 0.0],
+        ];
+        let tsa = TSA::create_from_matrix(distances, TSAConfig::create_default());
+        let best_known = BestKnown {
+            distance: None,
+            tour: Some(vec![1, 2, 3, 4]),
+        };
+        assert_eq!(tsa.get_best_known_distance(&best_known), Some(4.0));
+        assert!(tsa.get_gap(4.0) >= 0.0);
+    }
+
+    #[test]
+    fn tours_round_trip_through_original_ids() {
+        let data = [(10, 0.0, 0.0), (20, 3.0, 0.0), (30, 3.0, 4.0)]
+            .iter()
+            .map(|&(group, x, y)| DataNode {
+                group,
+                point: Point { x, y },
+            })
+            .collect::<Vec<_>>();
+        let mut tsa = TSA::create(data, TSAConfig::create_default());
+
+        let tour = Tour::create("tri.tour", vec![30, 10, 20]);
+        assert_eq!(tsa.evaluate_tour(&tour), Some(12.0));
+        assert_eq!(tsa.seed_from_tour(&tour), Some(12.0));
+        assert_eq!(tsa.get_current_distance(), 12.0);
+        assert_eq!(tsa.get_tour("tri.tour").nodes, vec![30, 10, 20]);
+
+        assert_eq!(
+            tsa.evaluate_tour(&Tour::create("bad", vec![10, 10, 20])),
+            None
+        );
+        assert_eq!(
+            tsa.evaluate_tour(&Tour::create("bad", vec![10, 20, 40])),
+            None
+        );
+        assert_eq!(tsa.evaluate_tour(&Tour::create("bad", vec![10, 20])), None);
+    }
+
+    #[test]
+    fn optimal_tour_beside_the_instance_gives_the_gap() {
+        let dir = std::env::temp_dir().join(format!("tsa_opt_tour_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let optimal = Tour::create("rect4.opt.tour", vec![1, 2, 3, 4]);
+        write_tour(dir.join("rect4.opt.tour"), &optimal).unwrap();
+        let mut registry = BestKnownRegistry::new();
+        assert!(registry.add_tour_beside(dir.join("rect4.tsp")).unwrap());
+        assert!(!registry.add_tour_beside(dir.join("other.tsp")).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let data = [(1, 0.0, 0.0), (2, 3.0, 0.0), (3, 3.0, 4.0), (4, 0.0, 4.0)]
+            .iter()
+            .map(|&(group, x, y)| DataNode {
+                group,
+                point: Point { x, y },
+            })
+            .collect::<Vec<_>>();
+        let mut tsa = TSA::create(data, TSAConfig::create_default());
+        let best_known = registry.get("rect4").unwrap();
+        assert_eq!(best_known.distance, None);
+        let optimum = tsa.get_best_known_distance(best_known).unwrap();
+        assert_eq!(optimum, 14.0);
+
+        let crossed = Tour::create("crossed.tour", vec![1, 3, 2, 4]);
+        assert_eq!(tsa.seed_from_tour(&crossed), Some(18.0));
+        assert!((tsa.get_gap(optimum) - 4.0 / 14.0 * 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn running_distance_tracks_the_tour() {
+        let operators = [
+            vec![(Operator::KSwap, 1.0)],
+            Operator::ALL
+                .iter()
+                .map(|operator| (*operator, 1.0))
+                .collect(),
+        ];
+        for operators in operators {
+            let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+            let config =
+                TSAConfig::<ExpCooling>::create(1.0, 100.0, 20000, 10).with_operators(operators);
+            let mut tsa = TSA::create(data, config);
+            let initial = tsa.get_current_distance();
+            for _ in 0..20000 {
+                tsa.gen_next_solution();
+            }
+            let distance = tsa.get_solution_distance(&tsa.solution);
+            assert!((tsa.get_current_distance() - distance).abs() < 1e-6);
+            assert!(distance < initial);
+        }
+    }
+
+    #[test]
+    fn two_opt_gets_close_to_the_optimum() {
+        let instance = data_retrieve::tsplib::load_tsplib("../data/inst_51_raw.txt").unwrap();
+        let config = TSAConfig::<ExpCooling>::create(0.5, 50.0, 200000, 10)
+            .with_operators(vec![(Operator::TwoOpt, 3.0), (Operator::OrOpt, 1.0)]);
+        let mut tsa = TSA::from_instance(instance, config).unwrap();
+        for _ in 0..200000 {
+            tsa.gen_next_solution();
+        }
+        assert!(tsa.get_gap(426.0) < 10.0);
+    }
+
+    #[test]
+    fn operator_stats_cover_every_move() {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let config = TSAConfig::<ExpCooling>::create(1.0, 100.0, 5000, 10)
+            .with_operators(
+                Operator::ALL
+                    .iter()
+                    .map(|operator| (*operator, 1.0))
+                    .collect(),
+            )
+            .with_operator_selection(OperatorSelection::Adaptive { reaction: 0.2 });
+        let mut tsa = TSA::create(data, config);
+        for _ in 0..5000 {
+            tsa.gen_next_solution();
+        }
+        let stats = tsa.get_operator_stats();
+        assert_eq!(
+            stats.iter().map(|(_, stats)| stats.tries).sum::<usize>(),
+            5000
+        );
+        assert!(stats
+            .iter()
+            .all(|(_, stats)| stats.improvements <= stats.acceptances
+                && stats.acceptances <= stats.tries));
+        let shares = tsa
+            .get_operator_shares()
+            .iter()
+            .map(|(_, share)| share)
+            .sum::<f64>();
+        assert!((shares - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn same_seed_same_trajectory() {
+        let run = |seed| {
+            let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+            let config = TSAConfig::<ExpCooling>::create(1.0, 100.0, 5000, 10)
+                .with_operators(
+                    Operator::ALL
+                        .iter()
+                        .map(|operator| (*operator, 1.0))
+                        .collect(),
+                )
+                .with_operator_selection(OperatorSelection::Adaptive { reaction: 0.2 })
+                .with_seed(seed);
+            let mut tsa = TSA::create(data, config);
+            let mut distances = vec![];
+            for _ in 0..5000 {
+                tsa.gen_next_solution();
+                distances.push(tsa.get_current_distance().to_bits());
+            }
+            assert_eq!(tsa.get_seed(), seed);
+            (distances, tsa.solution.clone())
+        };
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42).1, run(43).1);
+    }
+
+    #[test]
+    fn schedule_picked_at_runtime_matches_its_type() {
+        fn run<T: CoolingMethod>(config: TSAConfig<T>) -> (f64, Vec<usize>) {
+            let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+            let mut tsa = TSA::create(data, config.with_seed(3));
+            for _ in 0..2000 {
+                tsa.gen_next_solution();
+            }
+            (tsa.get_current_temperature(), tsa.solution.clone())
+        }
+        let named = TSAConfig::<DynCooling>::create(1.0, 100.0, 2000, 10)
+            .with_schedule(Schedule::from_name("cos").unwrap());
+        let typed = TSAConfig::<CosCooling>::create(1.0, 100.0, 2000, 10);
+        assert_eq!(run(named), run(typed));
+    }
+
+    #[test]
+    fn adaptive_schedule_cools_from_the_run() {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let config = TSAConfig::<DynCooling>::create(1.0, 100.0, 50000, 10)
+            .with_schedule(Schedule::Adaptive)
+            .with_seed(5);
+        let mut tsa = TSA::create(data, config);
+        let start = tsa.get_current_distance();
+        let mut temperatures = vec![];
+        for _ in 0..50000 {
+            tsa.gen_next_solution();
+            temperatures.push(tsa.get_current_temperature());
+        }
+        assert!(temperatures.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!(tsa.get_current_temperature() < 100.0);
+        assert!(tsa.get_best_distance() < start);
+    }
+
+    #[test]
+    fn calibration_scales_with_the_instance() {
+        let range = |scale: f64| {
+            let data = data_retrieve::load("../data/inst_51.txt")
+                .unwrap()
+                .into_iter()
+                .map(|node| DataNode {
+                    point: Point {
+                        x: node.point.x * scale,
+                        y: node.point.y * scale,
+                    },
+                    ..node
+                })
+                .collect();
+            let config = TSAConfig::<DynCooling>::create(1.0, 100.0, 1000, 10)
+                .with_schedule(Schedule::Cos)
+                .with_calibration(Calibration::default())
+                .with_seed(11);
+            let tsa = TSA::create(data, config);
+            assert_eq!(tsa.get_current_temperature(), tsa.get_temperature_range().0);
+            let cooling = Schedule::Cos.create(
+                tsa.get_temperature_range().0,
+                tsa.get_temperature_range().1,
+                1000,
+            );
+            assert_eq!(
+                tsa.config.cooling_method.get_next_temperature(500),
+                cooling.get_next_temperature(500)
+            );
+            tsa.get_temperature_range()
+        };
+        let (initial, fin) = range(1.0);
+        assert!(initial > fin && fin > 0.0);
+        let (scaled_initial, _) = range(100.0);
+        assert!(scaled_initial > 50.0 * initial && scaled_initial < 200.0 * initial);
+    }
+
+    #[test]
+    fn run_reports_why_it_stopped() {
+        let run = |stop: Option<StopCondition>| {
+            let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+            let config = TSAConfig::<ExpCooling>::create(1.0, 100.0, 20000, 10).with_seed(2);
+            let config = match stop {
+                Some(stop) => config.with_stop(stop),
+                None => config,
+            };
+            let mut tsa = TSA::create(data, config);
+            let reason = tsa.run();
+            assert_eq!(tsa.get_stop_reason(), Some(reason.clone()));
+            (reason, tsa)
+        };
+
+        let (reason, tsa) = run(None);
+        assert_eq!(reason, StopCondition::Iterations(20000));
+        assert_eq!(tsa.get_current_iter(), 20000);
+
+        let (reason, tsa) = run(Some(StopCondition::Iterations(500)));
+        assert_eq!(reason, StopCondition::Iterations(500));
+        assert_eq!(tsa.get_current_iter(), 500);
+
+        let target = StopCondition::TargetDistance(1000.0);
+        let (reason, tsa) = run(Some(target.clone()));
+        assert_eq!(reason, target);
+        assert!(tsa.get_best_distance() <= 1000.0);
+        assert!(tsa.get_current_iter() < 20000);
+
+        let limit = StopCondition::TimeLimit(Duration::ZERO);
+        let (reason, tsa) = run(Some(StopCondition::AnyOf(vec![
+            StopCondition::TargetDistance(0.0),
+            limit.clone(),
+        ])));
+        assert_eq!(reason, limit);
+        assert_eq!(tsa.get_current_iter(), 0);
+
+        let (reason, tsa) = run(Some(StopCondition::NoImprovement(300)));
+        assert_eq!(reason, StopCondition::NoImprovement(300));
+        assert_eq!(tsa.get_current_iter() - tsa.get_best_iter(), 300);
+
+        let cold = StopCondition::Temperature(50.0);
+        let (reason, tsa) = run(Some(cold.clone()));
+        assert_eq!(reason, cold);
+        assert!(tsa.get_current_temperature() < 50.0);
+    }
+
+    #[test]
+    fn reheats_after_stagnation_up_to_the_cap() {
+        let run = |reheat: Reheat| {
+            let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+            let config = TSAConfig::<ExpCooling>::create(0.5, 5.0, 30000, 10)
+                .with_reheat(reheat)
+                .with_seed(4);
+            let mut tsa = TSA::create(data, config);
+            let mut restarts_checked = 0;
+            while tsa.get_stop_reason().is_none() {
+                let before = tsa.get_current_temperature();
+                tsa.gen_next_solution();
+                if let Some(event) = tsa.get_reheats().last() {
+                    if event.iter == tsa.get_current_iter() {
+                        assert!(event.temperature > before);
+                        if event.restarted {
+                            assert_eq!(tsa.get_current_distance(), tsa.get_best_distance());
+                            assert_eq!(tsa.solution, tsa.get_best_solution());
+                            restarts_checked += 1;
+                        }
+                    }
+                }
+            }
+            assert_eq!(
+                tsa.get_current_distance(),
+                tsa.get_solution_distance(&tsa.solution)
+            );
+            // The reheats have faded out by the end of the run.
+            assert!((tsa.get_current_temperature() - 0.5).abs() < 1e-9);
+            (tsa.get_reheats().to_vec(), restarts_checked)
+        };
+
+        let (reheats, restarts) = run(Reheat::create(2000, 10.0, 3));
+        assert_eq!(reheats.len(), 3);
+        assert_eq!(restarts, 0);
+        assert!(reheats
+            .windows(2)
+            .all(|pair| pair[1].iter - pair[0].iter >= 2000));
+
+        let (reheats, restarts) = run(Reheat::create(2000, 10.0, 2).with_restart_from_best());
+        assert_eq!(reheats.len(), 2);
+        assert_eq!(restarts, 2);
+    }
+
+    #[test]
+    fn solves_a_generated_circle_to_its_known_optimum() {
+        let generator = Generator::Circle {
+            size: 30,
+            radius: 500.0,
+        };
+        let config = TSAConfig::<ExpCooling>::create(0.5, 200.0, 100000, 10)
+            .with_operators(vec![(Operator::TwoOpt, 1.0), (Operator::OrOpt, 1.0)])
+            .with_seed(4);
+        let mut tsa = TSA::from_instance(generator.instance(4), config).unwrap();
+        tsa.run();
+        let optimum = generator.known_optimum().unwrap();
+        assert_eq!(tsa.get_best_gap(optimum), 0.0);
+    }
+
+    #[test]
+    fn best_is_never_worse_than_any_visited_tour() {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let config = TSAConfig::<ExpCooling>::create(10.0, 1000.0, 5000, 10).with_seed(7);
+        let mut tsa = TSA::create(data, config);
+        let mut shortest = tsa.get_current_distance();
+        let mut shortest_iter = 0;
+        for iter in 1..=5000 {
+            tsa.gen_next_solution();
+            if tsa.get_current_distance() < shortest {
+                shortest = tsa.get_current_distance();
+                shortest_iter = iter;
+            }
+        }
+        assert_eq!(tsa.get_best_distance(), shortest);
+        assert_eq!(tsa.get_best_iter(), shortest_iter);
+        assert!(tsa.get_best_distance() <= tsa.get_current_distance());
+        let best = tsa.get_solution_distance(tsa.get_best_solution());
+        assert!((best - shortest).abs() < 1e-6);
+        assert_eq!(tsa.get_best_tour("best").nodes.len(), 51);
+    }
 }
@@ -0,0 +1,591 @@
+// Minimum-weight perfect matching on a complete graph, through Edmonds'
+// weighted blossom algorithm. This follows Joris van Rantwijk's
+// `mwmatching.py` (maximum-weight matching, O(n^3)), run for maximum
+// cardinality on complemented weights. Weights are scaled to integers so the
+// dual variables stay exact.
+
+const NONE: usize = usize::MAX;
+// Distances are kept to this many parts per unit when scaled to integers.
+const WEIGHT_SCALE: f64 = 1_000.0;
+
+struct Blossoms<'a> {
+    edges: &'a [(usize, usize, i64)],
+    vertices: usize,
+    // Endpoint `p` is vertex `endpoint[p]` of edge `p / 2`; `p ^ 1` is the
+    // other end.
+    endpoint: Vec<usize>,
+    // Endpoints leading away from each vertex.
+    neighbor_ends: Vec<Vec<usize>>,
+    // The remote endpoint a vertex is matched through.
+    mate: Vec<usize>,
+    // 0 free, 1 S, 2 T, 5 S while scanning, -1 dissolved.
+    label: Vec<i32>,
+    label_end: Vec<usize>,
+    in_blossom: Vec<usize>,
+    blossom_parent: Vec<usize>,
+    blossom_children: Vec<Vec<usize>>,
+    blossom_base: Vec<usize>,
+    blossom_ends: Vec<Vec<usize>>,
+    best_edge: Vec<usize>,
+    blossom_best_edges: Vec<Option<Vec<usize>>>,
+    unused_blossoms: Vec<usize>,
+    dual: Vec<i64>,
+    allow_edge: Vec<bool>,
+    queue: Vec<usize>,
+}
+
+fn wrap(index: isize, len: usize) -> usize {
+    index.rem_euclid(len as isize) as usize
+}
+
+impl<'a> Blossoms<'a> {
+    fn create(vertices: usize, edges: &'a [(usize, usize, i64)]) -> Self {
+        let max_weight = edges.iter().map(|edge| edge.2).max().unwrap_or(0).max(0);
+        let mut neighbor_ends = vec![vec![]; vertices];
+        let mut endpoint = Vec::with_capacity(2 * edges.len());
+        for (k, (i, j, _)) in edges.iter().enumerate() {
+            endpoint.push(*i);
+            endpoint.push(*j);
+            neighbor_ends[*i].push(2 * k + 1);
+            neighbor_ends[*j].push(2 * k);
+        }
+        let mut dual = vec![max_weight; vertices];
+        dual.extend(vec![0; vertices]);
+        let mut blossom_base = (0..vertices).collect::<Vec<_>>();
+        blossom_base.extend(vec![NONE; vertices]);
+        Self {
+            edges,
+            vertices,
+            endpoint,
+            neighbor_ends,
+            mate: vec![NONE; vertices],
+            label: vec![0; 2 * vertices],
+            label_end: vec![NONE; 2 * vertices],
+            in_blossom: (0..vertices).collect(),
+            blossom_parent: vec![NONE; 2 * vertices],
+            blossom_children: vec![vec![]; 2 * vertices],
+            blossom_base,
+            blossom_ends: vec![vec![]; 2 * vertices],
+            best_edge: vec![NONE; 2 * vertices],
+            blossom_best_edges: vec![None; 2 * vertices],
+            unused_blossoms: (vertices..2 * vertices).collect(),
+            dual,
+            allow_edge: vec![false; edges.len()],
+            queue: vec![],
+        }
+    }
+
+    fn slack(&self, k: usize) -> i64 {
+        let (i, j, weight) = self.edges[k];
+        self.dual[i] + self.dual[j] - 2 * weight
+    }
+
+    fn leaves(&self, b: usize) -> Vec<usize> {
+        if b < self.vertices {
+            return vec![b];
+        }
+        self.blossom_children[b]
+            .iter()
+            .flat_map(|child| self.leaves(*child))
+            .collect()
+    }
+
+    fn assign_label(&mut self, w: usize, t: i32, p: usize) {
+        let b = self.in_blossom[w];
+        self.label[w] = t;
+        self.label[b] = t;
+        self.label_end[w] = p;
+        self.label_end[b] = p;
+        self.best_edge[w] = NONE;
+        self.best_edge[b] = NONE;
+        if t == 1 {
+            let leaves = self.leaves(b);
+            self.queue.extend(leaves);
+        } else if t == 2 {
+            let base_mate = self.mate[self.blossom_base[b]];
+            self.assign_label(self.endpoint[base_mate], 1, base_mate ^ 1);
+        }
+    }
+
+    // Traces back from `v` and `w` to find a new blossom's base, or NONE if
+    // they lead to different roots (an augmenting path).
+    fn scan_blossom(&mut self, mut v: usize, mut w: usize) -> usize {
+        let mut path = vec![];
+        let mut base = NONE;
+        while v != NONE || w != NONE {
+            let mut b = self.in_blossom[v];
+            if self.label[b] & 4 != 0 {
+                base = self.blossom_base[b];
+                break;
+            }
+            path.push(b);
+            self.label[b] = 5;
+            if self.label_end[b] == NONE {
+                v = NONE;
+            } else {
+                v = self.endpoint[self.label_end[b]];
+                b = self.in_blossom[v];
+                v = self.endpoint[self.label_end[b]];
+            }
+            if w != NONE {
+                std::mem::swap(&mut v, &mut w);
+            }
+        }
+        for b in path {
+            self.label[b] = 1;
+        }
+        base
+    }
+
+    fn add_blossom(&mut self, base: usize, k: usize) {
+        let (mut v, mut w, _) = self.edges[k];
+        let bb = self.in_blossom[base];
+        let mut bv = self.in_blossom[v];
+        let mut bw = self.in_blossom[w];
+        let b = self.unused_blossoms.pop().unwrap();
+        self.blossom_base[b] = base;
+        self.blossom_parent[b] = NONE;
+        self.blossom_parent[bb] = b;
+
+        let mut path = vec![];
+        let mut ends = vec![];
+        while bv != bb {
+            self.blossom_parent[bv] = b;
+            path.push(bv);
+            ends.push(self.label_end[bv]);
+            v = self.endpoint[self.label_end[bv]];
+            bv = self.in_blossom[v];
+        }
+        path.push(bb);
+        path.reverse();
+        ends.reverse();
+        ends.push(2 * k);
+        while bw != bb {
+            self.blossom_parent[bw] = b;
+            path.push(bw);
+            ends.push(self.label_end[bw] ^ 1);
+            w = self.endpoint[self.label_end[bw]];
+            bw = self.in_blossom[w];
+        }
+
+        self.label[b] = 1;
+        self.label_end[b] = self.label_end[bb];
+        self.dual[b] = 0;
+        self.blossom_children[b] = path.clone();
+        self.blossom_ends[b] = ends;
+        for leaf in self.leaves(b) {
+            if self.label[self.in_blossom[leaf]] == 2 {
+                self.queue.push(leaf);
+            }
+            self.in_blossom[leaf] = b;
+        }
+
+        let mut best_edge_to = vec![NONE; 2 * self.vertices];
+        for bv in path {
+            let lists = match self.blossom_best_edges[bv].take() {
+                Some(list) => vec![list],
+                None => self
+                    .leaves(bv)
+                    .iter()
+                    .map(|leaf| self.neighbor_ends[*leaf].iter().map(|p| p / 2).collect())
+                    .collect(),
+            };
+            for list in lists {
+                for k in list {
+                    let (i, j, _) = self.edges[k];
+                    let bj = if self.in_blossom[j] == b {
+                        self.in_blossom[i]
+                    } else {
+                        self.in_blossom[j]
+                    };
+                    if bj != b
+                        && self.label[bj] == 1
+                        && (best_edge_to[bj] == NONE
+                            || self.slack(k) < self.slack(best_edge_to[bj]))
+                    {
+                        best_edge_to[bj] = k;
+                    }
+                }
+            }
+            self.best_edge[bv] = NONE;
+        }
+        let list = best_edge_to
+            .into_iter()
+            .filter(|k| *k != NONE)
+            .collect::<Vec<_>>();
+        self.best_edge[b] = NONE;
+        for k in list.iter() {
+            if self.best_edge[b] == NONE || self.slack(*k) < self.slack(self.best_edge[b]) {
+                self.best_edge[b] = *k;
+            }
+        }
+        self.blossom_best_edges[b] = Some(list);
+    }
+
+    fn expand_blossom(&mut self, b: usize, end_stage: bool) {
+        for s in self.blossom_children[b].clone() {
+            self.blossom_parent[s] = NONE;
+            if s < self.vertices {
+                self.in_blossom[s] = s;
+            } else if end_stage && self.dual[s] == 0 {
+                self.expand_blossom(s, end_stage);
+            } else {
+                for leaf in self.leaves(s) {
+                    self.in_blossom[leaf] = s;
+                }
+            }
+        }
+
+        if !end_stage && self.label[b] == 2 {
+            let children = self.blossom_children[b].clone();
+            let ends = self.blossom_ends[b].clone();
+            let len = children.len();
+            let entry_child = self.in_blossom[self.endpoint[self.label_end[b] ^ 1]];
+            let mut j = children
+                .iter()
+                .position(|child| *child == entry_child)
+                .unwrap() as isize;
+            let (step, trick): (isize, usize) = if j & 1 != 0 {
+                j -= len as isize;
+                (1, 0)
+            } else {
+                (-1, 1)
+            };
+            let mut p = self.label_end[b];
+            while j != 0 {
+                self.label[self.endpoint[p ^ 1]] = 0;
+                let end = ends[wrap(j - trick as isize, len)];
+                self.label[self.endpoint[end ^ trick ^ 1]] = 0;
+                self.assign_label(self.endpoint[p ^ 1], 2, p);
+                self.allow_edge[end / 2] = true;
+                j += step;
+                p = ends[wrap(j - trick as isize, len)] ^ trick;
+                self.allow_edge[p / 2] = true;
+                j += step;
+            }
+            let bv = children[wrap(j, len)];
+            self.label[self.endpoint[p ^ 1]] = 2;
+            self.label[bv] = 2;
+            self.label_end[self.endpoint[p ^ 1]] = p;
+            self.label_end[bv] = p;
+            self.best_edge[bv] = NONE;
+            j += step;
+            while children[wrap(j, len)] != entry_child {
+                let bv = children[wrap(j, len)];
+                if self.label[bv] == 1 {
+                    j += step;
+                    continue;
+                }
+                let labelled = self
+                    .leaves(bv)
+                    .into_iter()
+                    .find(|leaf| self.label[*leaf] != 0);
+                if let Some(v) = labelled {
+                    self.label[v] = 0;
+                    self.label[self.endpoint[self.mate[self.blossom_base[bv]]]] = 0;
+                    self.assign_label(v, 2, self.label_end[v]);
+                }
+                j += step;
+            }
+        }
+
+        self.label[b] = -1;
+        self.label_end[b] = NONE;
+        self.blossom_children[b] = vec![];
+        self.blossom_ends[b] = vec![];
+        self.blossom_base[b] = NONE;
+        self.blossom_best_edges[b] = None;
+        self.best_edge[b] = NONE;
+        self.unused_blossoms.push(b);
+    }
+
+    // Swaps matched and unmatched edges inside `b` so that `v` becomes its
+    // base.
+    fn augment_blossom(&mut self, b: usize, v: usize) {
+        let mut t = v;
+        while self.blossom_parent[t] != b {
+            t = self.blossom_parent[t];
+        }
+        if t >= self.vertices {
+            self.augment_blossom(t, v);
+        }
+        let len = self.blossom_children[b].len();
+        let i = self.blossom_children[b]
+            .iter()
+            .position(|child| *child == t)
+            .unwrap();
+        let mut j = i as isize;
+        let (step, trick): (isize, usize) = if i & 1 != 0 {
+            j -= len as isize;
+            (1, 0)
+        } else {
+            (-1, 1)
+        };
+        while j != 0 {
+            j += step;
+            let t = self.blossom_children[b][wrap(j, len)];
+            let p = self.blossom_ends[b][wrap(j - trick as isize, len)] ^ trick;
+            if t >= self.vertices {
+                self.augment_blossom(t, self.endpoint[p]);
+            }
+            j += step;
+            let t = self.blossom_children[b][wrap(j, len)];
+            if t >= self.vertices {
+                self.augment_blossom(t, self.endpoint[p ^ 1]);
+            }
+            self.mate[self.endpoint[p]] = p ^ 1;
+            self.mate[self.endpoint[p ^ 1]] = p;
+        }
+        self.blossom_children[b].rotate_left(i);
+        self.blossom_ends[b].rotate_left(i);
+        self.blossom_base[b] = self.blossom_base[self.blossom_children[b][0]];
+    }
+
+    fn augment_matching(&mut self, k: usize) {
+        let (v, w, _) = self.edges[k];
+        for (mut s, mut p) in [(v, 2 * k + 1), (w, 2 * k)] {
+            loop {
+                let bs = self.in_blossom[s];
+                if bs >= self.vertices {
+                    self.augment_blossom(bs, s);
+                }
+                self.mate[s] = p;
+                if self.label_end[bs] == NONE {
+                    break;
+                }
+                let t = self.endpoint[self.label_end[bs]];
+                let bt = self.in_blossom[t];
+                s = self.endpoint[self.label_end[bt]];
+                let j = self.endpoint[self.label_end[bt] ^ 1];
+                if bt >= self.vertices {
+                    self.augment_blossom(bt, j);
+                }
+                self.mate[j] = self.label_end[bt];
+                p = self.label_end[bt] ^ 1;
+            }
+        }
+    }
+
+    // One stage: grows alternating trees until the matching is augmented,
+    // or returns false when no augmenting path is left.
+    fn stage(&mut self) -> bool {
+        self.label.iter_mut().for_each(|label| *label = 0);
+        self.best_edge.iter_mut().for_each(|edge| *edge = NONE);
+        for b in self.vertices..2 * self.vertices {
+            self.blossom_best_edges[b] = None;
+        }
+        self.allow_edge.iter_mut().for_each(|allow| *allow = false);
+        self.queue.clear();
+        for v in 0..self.vertices {
+            if self.mate[v] == NONE && self.label[self.in_blossom[v]] == 0 {
+                self.assign_label(v, 1, NONE);
+            }
+        }
+
+        loop {
+            while let Some(v) = self.queue.pop() {
+                for p in self.neighbor_ends[v].clone() {
+                    let k = p / 2;
+                    let w = self.endpoint[p];
+                    if self.in_blossom[v] == self.in_blossom[w] {
+                        continue;
+                    }
+                    let mut slack = 0;
+                    if !self.allow_edge[k] {
+                        slack = self.slack(k);
+                        if slack <= 0 {
+                            self.allow_edge[k] = true;
+                        }
+                    }
+                    if self.allow_edge[k] {
+                        if self.label[self.in_blossom[w]] == 0 {
+                            self.assign_label(w, 2, p ^ 1);
+                        } else if self.label[self.in_blossom[w]] == 1 {
+                            let base = self.scan_blossom(v, w);
+                            if base != NONE {
+                                self.add_blossom(base, k);
+                            } else {
+                                self.augment_matching(k);
+                                return true;
+                            }
+                        } else if self.label[w] == 0 {
+                            self.label[w] = 2;
+                            self.label_end[w] = p ^ 1;
+                        }
+                    } else if self.label[self.in_blossom[w]] == 1 {
+                        let b = self.in_blossom[v];
+                        if self.best_edge[b] == NONE || slack < self.slack(self.best_edge[b]) {
+                            self.best_edge[b] = k;
+                        }
+                    } else if self.label[w] == 0
+                        && (self.best_edge[w] == NONE || slack < self.slack(self.best_edge[w]))
+                    {
+                        self.best_edge[w] = k;
+                    }
+                }
+            }
+
+            // No tight edge left: move the duals by the largest step that
+            // keeps them feasible.
+            let mut delta: Option<(i64, u8, usize)> = None;
+            let mut consider = |value: i64, kind: u8, target: usize| {
+                if delta.is_none_or(|(best, _, _)| value < best) {
+                    delta = Some((value, kind, target));
+                }
+            };
+            for v in 0..self.vertices {
+                if self.label[self.in_blossom[v]] == 0 && self.best_edge[v] != NONE {
+                    consider(self.slack(self.best_edge[v]), 2, self.best_edge[v]);
+                }
+            }
+            for b in 0..2 * self.vertices {
+                if self.blossom_parent[b] == NONE && self.label[b] == 1 && self.best_edge[b] != NONE
+                {
+                    consider(self.slack(self.best_edge[b]) / 2, 3, self.best_edge[b]);
+                }
+            }
+            for b in self.vertices..2 * self.vertices {
+                if self.blossom_base[b] != NONE
+                    && self.blossom_parent[b] == NONE
+                    && self.label[b] == 2
+                {
+                    consider(self.dual[b], 4, b);
+                }
+            }
+            let (delta, kind, target) = delta.unwrap_or_else(|| {
+                let min_dual = self.dual[..self.vertices]
+                    .iter()
+                    .copied()
+                    .min()
+                    .unwrap_or(0);
+                (min_dual.max(0), 1, NONE)
+            });
+
+            for v in 0..self.vertices {
+                match self.label[self.in_blossom[v]] {
+                    1 => self.dual[v] -= delta,
+                    2 => self.dual[v] += delta,
+                    _ => {}
+                }
+            }
+            for b in self.vertices..2 * self.vertices {
+                if self.blossom_base[b] != NONE && self.blossom_parent[b] == NONE {
+                    match self.label[b] {
+                        1 => self.dual[b] += delta,
+                        2 => self.dual[b] -= delta,
+                        _ => {}
+                    }
+                }
+            }
+
+            match kind {
+                1 => return false,
+                2 => {
+                    self.allow_edge[target] = true;
+                    let (mut i, j, _) = self.edges[target];
+                    if self.label[self.in_blossom[i]] == 0 {
+                        i = j;
+                    }
+                    self.queue.push(i);
+                }
+                3 => {
+                    self.allow_edge[target] = true;
+                    self.queue.push(self.edges[target].0);
+                }
+                _ => self.expand_blossom(target, false),
+            }
+        }
+    }
+
+    fn run(mut self) -> Vec<usize> {
+        for _ in 0..self.vertices {
+            if !self.stage() {
+                break;
+            }
+            for b in self.vertices..2 * self.vertices {
+                if self.blossom_parent[b] == NONE
+                    && self.blossom_base[b] != NONE
+                    && self.label[b] == 1
+                    && self.dual[b] == 0
+                {
+                    self.expand_blossom(b, true);
+                }
+            }
+        }
+        self.mate
+            .iter()
+            .map(|p| if *p == NONE { NONE } else { self.endpoint[*p] })
+            .collect()
+    }
+}
+
+/// Pairs up `nodes` (an even number of them) so the summed distance between
+/// partners is minimal. Returns the pairs.
+pub fn min_weight_perfect_matching(distances: &[Vec<f64>], nodes: &[usize]) -> Vec<(usize, usize)> {
+    let scaled = |a: usize, b: usize| (distances[nodes[a]][nodes[b]] * WEIGHT_SCALE).round() as i64;
+    let max_weight = (0..nodes.len())
+        .flat_map(|a| (a + 1..nodes.len()).map(move |b| (a, b)))
+        .map(|(a, b)| scaled(a, b))
+        .max()
+        .unwrap_or(0);
+    // A maximum-cardinality matching is perfect on a complete graph, so
+    // maximizing the complemented weight minimizes the distance.
+    let edges = (0..nodes.len())
+        .flat_map(|a| (a + 1..nodes.len()).map(move |b| (a, b)))
+        .map(|(a, b)| (a, b, max_weight + 1 - scaled(a, b)))
+        .collect::<Vec<_>>();
+    let mate = Blossoms::create(nodes.len(), &edges).run();
+    (0..nodes.len())
+        .filter(|a| mate[*a] != NONE && *a < mate[*a])
+        .map(|a| (nodes[a], nodes[mate[a]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use crate::matching::min_weight_perfect_matching;
+
+    // Cheapest perfect matching of the nodes in `mask`, by brute force.
+    fn brute_force(distances: &[Vec<f64>], mask: usize) -> f64 {
+        if mask == 0 {
+            return 0.0;
+        }
+        let first = mask.trailing_zeros() as usize;
+        let rest = mask & !(1 << first);
+        (0..distances.len())
+            .filter(|other| rest & (1 << other) != 0)
+            .map(|other| distances[first][other] + brute_force(distances, rest & !(1 << other)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(17);
+        for size in [0, 2, 4, 6, 8, 10, 12] {
+            for _ in 0..20 {
+                let points = (0..size)
+                    .map(|_| (rng.gen_range(0..100) as f64, rng.gen_range(0..100) as f64))
+                    .collect::<Vec<_>>();
+                let distances = points
+                    .iter()
+                    .map(|a| {
+                        points
+                            .iter()
+                            .map(|b| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt().round())
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                let nodes = (0..size).collect::<Vec<_>>();
+                let pairs = min_weight_perfect_matching(&distances, &nodes);
+                assert_eq!(pairs.len(), size / 2);
+                let mut covered = pairs.iter().flat_map(|(a, b)| [*a, *b]).collect::<Vec<_>>();
+                covered.sort();
+                assert_eq!(covered, nodes);
+                let weight = pairs.iter().map(|(a, b)| distances[*a][*b]).sum::<f64>();
+                assert_eq!(weight, brute_force(&distances, (1 << size) - 1));
+            }
+        }
+    }
+}
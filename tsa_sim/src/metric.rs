@@ -0,0 +1,125 @@
+use data_retrieve::{tsplib::EdgeWeightType, Point};
+
+/// Distance functions as defined by TSPLIB, so tour lengths are comparable
+/// with the published optima.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    #[default]
+    Euc2D,
+    Ceil2D,
+    Att,
+    Geo,
+    Man2D,
+    Max2D,
+}
+
+const EARTH_RADIUS: f64 = 6378.388;
+// TSPLIB defines GEO with this truncated value of pi, not the exact constant.
+#[allow(clippy::approx_constant)]
+const GEO_PI: f64 = 3.141592;
+
+fn nint(value: f64) -> f64 {
+    (value + 0.5).floor()
+}
+
+fn euclidian(a: &Point, b: &Point) -> f64 {
+    let x_diff = b.x - a.x;
+    let y_diff = b.y - a.y;
+    let sum = x_diff * x_diff + y_diff * y_diff;
+
+    sum.sqrt()
+}
+
+// Latitude/longitude in radians from the DDD.MM format used by GEO instances.
+fn geo_radians(value: f64) -> f64 {
+    let deg = value.trunc();
+    let min = value - deg;
+    GEO_PI * (deg + 5.0 * min / 3.0) / 180.0
+}
+
+impl Metric {
+    pub fn from_edge_weight_type(edge_weight_type: EdgeWeightType) -> Option<Self> {
+        match edge_weight_type {
+            EdgeWeightType::Euc2D => Some(Self::Euc2D),
+            EdgeWeightType::Ceil2D => Some(Self::Ceil2D),
+            EdgeWeightType::Att => Some(Self::Att),
+            EdgeWeightType::Geo => Some(Self::Geo),
+            EdgeWeightType::Man2D => Some(Self::Man2D),
+            EdgeWeightType::Max2D => Some(Self::Max2D),
+            _ => None,
+        }
+    }
+
+    pub fn distance(&self, a: &Point, b: &Point) -> f64 {
+        let x_diff = (b.x - a.x).abs();
+        let y_diff = (b.y - a.y).abs();
+        match self {
+            Metric::Euc2D => nint(euclidian(a, b)),
+            Metric::Ceil2D => euclidian(a, b).ceil(),
+            Metric::Att => {
+                let r = ((x_diff * x_diff + y_diff * y_diff) / 10.0).sqrt();
+                let t = nint(r);
+                if t < r {
+                    t + 1.0
+                } else {
+                    t
+                }
+            }
+            Metric::Geo => {
+                let (lat_a, lon_a) = (geo_radians(a.x), geo_radians(a.y));
+                let (lat_b, lon_b) = (geo_radians(b.x), geo_radians(b.y));
+                let q1 = (lon_a - lon_b).cos();
+                let q2 = (lat_a - lat_b).cos();
+                let q3 = (lat_a + lat_b).cos();
+                let arc = (0.5 * ((1.0 + q1) * q2 - (1.0 - q1) * q3)).acos();
+                (EARTH_RADIUS * arc + 1.0).trunc()
+            }
+            Metric::Man2D => nint(x_diff + y_diff),
+            Metric::Max2D => nint(x_diff).max(nint(y_diff)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_retrieve::Point;
+
+    use crate::metric::Metric;
+
+    #[test]
+    fn euc_2d_rounds_to_nearest_integer() {
+        let a = Point { x: 37.0, y: 52.0 };
+        let b = Point { x: 49.0, y: 49.0 };
+        assert_eq!(Metric::Euc2D.distance(&a, &b), 12.0);
+        assert_eq!(Metric::Ceil2D.distance(&a, &b), 13.0);
+    }
+
+    #[test]
+    fn manhattan_and_maximum() {
+        let a = Point { x: 0.0, y: 0.0 };
+        let b = Point { x: 3.0, y: 7.0 };
+        assert_eq!(Metric::Man2D.distance(&a, &b), 10.0);
+        assert_eq!(Metric::Max2D.distance(&a, &b), 7.0);
+    }
+
+    #[test]
+    fn att_rounds_up_pseudo_euclidian() {
+        let a = Point {
+            x: 6734.0,
+            y: 1453.0,
+        };
+        let b = Point { x: 2233.0, y: 10.0 };
+        // sqrt((4501^2 + 1443^2) / 10) = 1494.69..., always rounded up.
+        assert_eq!(Metric::Att.distance(&a, &b), 1495.0);
+    }
+
+    #[test]
+    fn geo_uses_degrees_and_minutes() {
+        // First three cities of burma14.
+        let a = Point { x: 16.47, y: 96.10 };
+        let b = Point { x: 16.47, y: 94.44 };
+        let c = Point { x: 20.09, y: 92.54 };
+        assert_eq!(Metric::Geo.distance(&a, &b), 153.0);
+        assert_eq!(Metric::Geo.distance(&a, &c), 510.0);
+    }
+}
@@ -0,0 +1,485 @@
+use std::fmt::{self, Display};
+
+use rand::{seq::SliceRandom, Rng};
+
+/// A change to a tour that can be priced before it is made. Distances are
+/// assumed symmetric, as in every TSPLIB `TSP` instance.
+pub trait Move {
+    /// Change in tour length if the move were applied to `solution`.
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64;
+    fn apply(&self, solution: &mut [usize]);
+    /// Reverts [`Move::apply`] on the tour it was applied to.
+    fn undo(&self, solution: &mut [usize]);
+}
+
+/// Draws random moves of one kind on a tour.
+pub trait Neighborhood {
+    type Move: Move;
+
+    /// A random move on `solution`, which must have at least
+    /// [`MIN_TOUR_SIZE`] nodes.
+    fn random_move<R: Rng>(&self, solution: &[usize], rng: &mut R) -> Self::Move;
+}
+
+/// Smallest tour the operators can work on. Below it every tour has the
+/// same length.
+pub const MIN_TOUR_SIZE: usize = 4;
+
+const MAX_SWAPS: usize = 5;
+const MAX_OR_OPT_SEGMENT: usize = 3;
+const MAX_SCRAMBLE_SEGMENT: usize = 8;
+
+fn previous(index: usize, size: usize) -> usize {
+    (index + size - 1) % size
+}
+
+/// Reverses the positions `i..=j`, the 2-opt move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reversal {
+    pub i: usize,
+    pub j: usize,
+}
+
+impl Move for Reversal {
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        let size = solution.len();
+        let before = solution[previous(self.i, size)];
+        let after = solution[(self.j + 1) % size];
+        let first = solution[self.i];
+        let last = solution[self.j];
+        distances[before][last] + distances[first][after]
+            - distances[before][first]
+            - distances[last][after]
+    }
+
+    fn apply(&self, solution: &mut [usize]) {
+        solution[self.i..=self.j].reverse();
+    }
+
+    fn undo(&self, solution: &mut [usize]) {
+        self.apply(solution);
+    }
+}
+
+/// Swaps the adjacent segments `i..j` and `j..k`, the 3-opt move that
+/// keeps both segments' orientation. Or-opt and insertion are the cases
+/// where one segment is short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentExchange {
+    pub i: usize,
+    pub j: usize,
+    pub k: usize,
+}
+
+impl Move for SegmentExchange {
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        let size = solution.len();
+        let before = solution[previous(self.i, size)];
+        let after = solution[self.k % size];
+        let (first_start, first_end) = (solution[self.i], solution[self.j - 1]);
+        let (second_start, second_end) = (solution[self.j], solution[self.k - 1]);
+        distances[before][second_start]
+            + distances[second_end][first_start]
+            + distances[first_end][after]
+            - distances[before][first_start]
+            - distances[first_end][second_start]
+            - distances[second_end][after]
+    }
+
+    fn apply(&self, solution: &mut [usize]) {
+        solution[self.i..self.k].rotate_left(self.j - self.i);
+    }
+
+    fn undo(&self, solution: &mut [usize]) {
+        solution[self.i..self.k].rotate_right(self.j - self.i);
+    }
+}
+
+/// Shuffles the positions `i..=j`: position `i + p` gets the node that was
+/// at `i + order[p]`. The segment never covers the whole tour.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scramble {
+    pub i: usize,
+    pub j: usize,
+    pub order: Vec<usize>,
+}
+
+impl Scramble {
+    // The node at offset `p` of the segment once the move is applied.
+    fn node_at(&self, solution: &[usize], p: usize) -> usize {
+        solution[self.i + self.order[p]]
+    }
+}
+
+impl Move for Scramble {
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        let size = solution.len();
+        let before = solution[previous(self.i, size)];
+        let after = solution[(self.j + 1) % size];
+        let len = self.order.len();
+        let old = distances[before][solution[self.i]]
+            + (self.i..self.j)
+                .map(|index| distances[solution[index]][solution[index + 1]])
+                .sum::<f64>()
+            + distances[solution[self.j]][after];
+        let new = distances[before][self.node_at(solution, 0)]
+            + (0..len - 1)
+                .map(|p| distances[self.node_at(solution, p)][self.node_at(solution, p + 1)])
+                .sum::<f64>()
+            + distances[self.node_at(solution, len - 1)][after];
+        new - old
+    }
+
+    fn apply(&self, solution: &mut [usize]) {
+        let segment = solution[self.i..=self.j].to_vec();
+        for (p, offset) in self.order.iter().enumerate() {
+            solution[self.i + p] = segment[*offset];
+        }
+    }
+
+    fn undo(&self, solution: &mut [usize]) {
+        let segment = solution[self.i..=self.j].to_vec();
+        for (p, offset) in self.order.iter().enumerate() {
+            solution[self.i + offset] = segment[p];
+        }
+    }
+}
+
+/// A chain of position swaps, kept as the positions it changes instead of a
+/// new tour.
+#[derive(Debug, Clone, Default)]
+pub struct KSwap {
+    // (position, node before, node after) for each position the chain
+    // touches, each position once.
+    changes: Vec<(usize, usize, usize)>,
+}
+
+impl KSwap {
+    /// Draws `qtd` swaps of two distinct positions, applied one after the
+    /// other.
+    pub fn random<R: Rng>(solution: &[usize], qtd: usize, rng: &mut R) -> Self {
+        let size = solution.len();
+        let mut swap = Self::default();
+        for _ in 0..qtd {
+            let mut index_1: usize;
+            let mut index_2: usize;
+            loop {
+                index_1 = rng.gen_range(0..size);
+                index_2 = rng.gen_range(0..size);
+                if index_1 != index_2 {
+                    break;
+                }
+            }
+            swap.swap(solution, index_1, index_2);
+        }
+        swap
+    }
+
+    pub fn swap(&mut self, solution: &[usize], index_1: usize, index_2: usize) {
+        let value_1 = self.node_at(solution, index_1);
+        let value_2 = self.node_at(solution, index_2);
+        self.set(solution, index_1, value_2);
+        self.set(solution, index_2, value_1);
+    }
+
+    fn set(&mut self, solution: &[usize], index: usize, node: usize) {
+        match self
+            .changes
+            .iter_mut()
+            .find(|(position, _, _)| *position == index)
+        {
+            Some(change) => change.2 = node,
+            None => self.changes.push((index, solution[index], node)),
+        }
+    }
+
+    // The node at `index` once the move is applied.
+    fn node_at(&self, solution: &[usize], index: usize) -> usize {
+        self.changes
+            .iter()
+            .find(|(position, _, _)| *position == index)
+            .map_or(solution[index], |(_, _, node)| *node)
+    }
+}
+
+impl Move for KSwap {
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        let size = solution.len();
+        // Edge `e` joins positions `e` and `e + 1`; each changed position
+        // touches the edge before it and its own.
+        let mut edges = Vec::with_capacity(2 * self.changes.len());
+        for (position, _, _) in self.changes.iter() {
+            for edge in [previous(*position, size), *position] {
+                if !edges.contains(&edge) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        edges
+            .iter()
+            .map(|edge| {
+                let next = (edge + 1) % size;
+                let before = distances[solution[*edge]][solution[next]];
+                let after = distances[self.node_at(solution, *edge)][self.node_at(solution, next)];
+                after - before
+            })
+            .sum()
+    }
+
+    fn apply(&self, solution: &mut [usize]) {
+        for (position, _, node) in self.changes.iter() {
+            solution[*position] = *node;
+        }
+    }
+
+    fn undo(&self, solution: &mut [usize]) {
+        for (position, node, _) in self.changes.iter() {
+            solution[*position] = *node;
+        }
+    }
+}
+
+/// The neighborhoods [`crate::TSAConfig`] can draw moves from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// Reverses a random stretch of the tour.
+    TwoOpt,
+    /// Moves a segment of two or three nodes elsewhere in the tour.
+    OrOpt,
+    /// Moves a single node elsewhere in the tour.
+    Insertion,
+    /// Swaps two adjacent segments of any length.
+    ThreeOpt,
+    /// Swaps 1 to 5 random pairs of nodes.
+    KSwap,
+    /// Shuffles a random stretch of 3 to 8 nodes.
+    Scramble,
+}
+
+impl Operator {
+    pub const ALL: [Operator; 6] = [
+        Operator::TwoOpt,
+        Operator::OrOpt,
+        Operator::Insertion,
+        Operator::ThreeOpt,
+        Operator::KSwap,
+        Operator::Scramble,
+    ];
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operator::TwoOpt => "2-opt",
+            Operator::OrOpt => "or-opt",
+            Operator::Insertion => "insertion",
+            Operator::ThreeOpt => "3-opt",
+            Operator::KSwap => "k-swap",
+            Operator::Scramble => "scramble",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Any of the moves drawn by an [`Operator`].
+#[derive(Debug, Clone)]
+pub enum OperatorMove {
+    Reversal(Reversal),
+    SegmentExchange(SegmentExchange),
+    KSwap(KSwap),
+    Scramble(Scramble),
+}
+
+impl Move for OperatorMove {
+    fn delta(&self, distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        match self {
+            OperatorMove::Reversal(reversal) => reversal.delta(distances, solution),
+            OperatorMove::SegmentExchange(exchange) => exchange.delta(distances, solution),
+            OperatorMove::KSwap(swap) => swap.delta(distances, solution),
+            OperatorMove::Scramble(scramble) => scramble.delta(distances, solution),
+        }
+    }
+
+    fn apply(&self, solution: &mut [usize]) {
+        match self {
+            OperatorMove::Reversal(reversal) => reversal.apply(solution),
+            OperatorMove::SegmentExchange(exchange) => exchange.apply(solution),
+            OperatorMove::KSwap(swap) => swap.apply(solution),
+            OperatorMove::Scramble(scramble) => scramble.apply(solution),
+        }
+    }
+
+    fn undo(&self, solution: &mut [usize]) {
+        match self {
+            OperatorMove::Reversal(reversal) => reversal.undo(solution),
+            OperatorMove::SegmentExchange(exchange) => exchange.undo(solution),
+            OperatorMove::KSwap(swap) => swap.undo(solution),
+            OperatorMove::Scramble(scramble) => scramble.undo(solution),
+        }
+    }
+}
+
+// Moves the `len` nodes starting at `start` between the nodes at `target`
+// and `target + 1`, written as an exchange with the nodes in between.
+fn random_relocation<R: Rng>(size: usize, len: usize, rng: &mut R) -> SegmentExchange {
+    let start = rng.gen_range(0..=size - len);
+    loop {
+        let target = rng.gen_range(0..size);
+        if target + 1 < start {
+            return SegmentExchange {
+                i: target + 1,
+                j: start,
+                k: start + len,
+            };
+        }
+        // Past the end of a segment that starts the tour, the target edge
+        // wraps back onto the segment.
+        if target >= start + len && !(start == 0 && target == size - 1) {
+            return SegmentExchange {
+                i: start,
+                j: start + len,
+                k: target + 1,
+            };
+        }
+    }
+}
+
+impl Neighborhood for Operator {
+    type Move = OperatorMove;
+
+    fn random_move<R: Rng>(&self, solution: &[usize], rng: &mut R) -> OperatorMove {
+        let size = solution.len();
+        match self {
+            Operator::TwoOpt => loop {
+                let i = rng.gen_range(0..size - 1);
+                let j = rng.gen_range(i + 1..size);
+                // Reversing the whole tour changes nothing.
+                if !(i == 0 && j == size - 1) {
+                    return OperatorMove::Reversal(Reversal { i, j });
+                }
+            },
+            Operator::OrOpt => {
+                let len = rng.gen_range(2..=MAX_OR_OPT_SEGMENT.min(size - 2));
+                OperatorMove::SegmentExchange(random_relocation(size, len, rng))
+            }
+            Operator::Insertion => OperatorMove::SegmentExchange(random_relocation(size, 1, rng)),
+            Operator::ThreeOpt => loop {
+                let mut cuts = [
+                    rng.gen_range(0..=size),
+                    rng.gen_range(0..=size),
+                    rng.gen_range(0..=size),
+                ];
+                cuts.sort();
+                let [i, j, k] = cuts;
+                // Exchanging the two halves of the whole tour only rotates it.
+                if i < j && j < k && !(i == 0 && k == size) {
+                    return OperatorMove::SegmentExchange(SegmentExchange { i, j, k });
+                }
+            },
+            Operator::KSwap => {
+                let qtd = rng.gen_range(1..=MAX_SWAPS);
+                OperatorMove::KSwap(KSwap::random(solution, qtd, rng))
+            }
+            Operator::Scramble => {
+                // Short of the whole tour, so the segment has neighbours.
+                let len = rng.gen_range(3..=MAX_SCRAMBLE_SEGMENT.min(size - 1));
+                let i = rng.gen_range(0..=size - len);
+                let mut order = (0..len).collect::<Vec<_>>();
+                order.shuffle(rng);
+                OperatorMove::Scramble(Scramble {
+                    i,
+                    j: i + len - 1,
+                    order,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use crate::moves::{Move, Neighborhood, Operator, OperatorMove, MIN_TOUR_SIZE};
+
+    fn tour_length(distances: &[Vec<f64>], solution: &[usize]) -> f64 {
+        (0..solution.len())
+            .map(|index| distances[solution[index]][solution[(index + 1) % solution.len()]])
+            .sum()
+    }
+
+    #[test]
+    fn delta_matches_full_evaluation() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for size in [MIN_TOUR_SIZE, 5, 6, 9, 40] {
+            let distances = (0..size)
+                .map(|i| {
+                    (0..size)
+                        .map(|j| ((i * 7 + j * 7) % 13 + i.abs_diff(j)) as f64)
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let mut solution = (0..size).collect::<Vec<usize>>();
+            solution.shuffle(&mut rng);
+            for operator in Operator::ALL {
+                for _ in 0..200 {
+                    let candidate = operator.random_move(&solution, &mut rng);
+                    let before = tour_length(&distances, &solution);
+                    let delta = candidate.delta(&distances, &solution);
+                    candidate.apply(&mut solution);
+                    let after = tour_length(&distances, &solution);
+                    assert!(
+                        (before + delta - after).abs() < 1e-9,
+                        "{:?} {:?}",
+                        operator,
+                        candidate
+                    );
+                }
+            }
+            let mut sorted = solution.clone();
+            sorted.sort();
+            assert_eq!(sorted, (0..size).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn undo_restores_the_tour() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut solution = (0..12).collect::<Vec<usize>>();
+        solution.shuffle(&mut rng);
+        for operator in Operator::ALL {
+            for _ in 0..50 {
+                let original = solution.clone();
+                let candidate = operator.random_move(&solution, &mut rng);
+                candidate.apply(&mut solution);
+                candidate.undo(&mut solution);
+                assert_eq!(solution, original, "{:?}", candidate);
+            }
+        }
+    }
+
+    #[test]
+    fn scramble_only_shuffles_its_segment() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for size in [MIN_TOUR_SIZE, 5, 30] {
+            let solution = (0..size).collect::<Vec<usize>>();
+            for _ in 0..100 {
+                let OperatorMove::Scramble(scramble) =
+                    Operator::Scramble.random_move(&solution, &mut rng)
+                else {
+                    panic!("scramble drew another move");
+                };
+                assert!(scramble.j - scramble.i + 1 < size);
+                let mut scrambled = solution.clone();
+                scramble.apply(&mut scrambled);
+                let (i, j) = (scramble.i, scramble.j);
+                assert_eq!(scrambled[..i], solution[..i]);
+                assert_eq!(scrambled[j + 1..], solution[j + 1..]);
+                let mut segment = scrambled[i..=j].to_vec();
+                segment.sort();
+                assert_eq!(segment, solution[i..=j]);
+            }
+        }
+    }
+}
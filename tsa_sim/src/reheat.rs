@@ -0,0 +1,40 @@
+/// Gets a stagnating run out of its local optimum: after `stagnation`
+/// iterations with neither a new best tour nor a reheat, the temperature is
+/// multiplied by `factor`. The extra heat fades out over the rest of the
+/// run, which still ends at the schedule's final temperature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reheat {
+    pub stagnation: usize,
+    /// Above 1.0 to reheat; 1.0 only restarts, if `restart_from_best`.
+    pub factor: f64,
+    /// Reheats allowed over the run.
+    pub max_reheats: usize,
+    /// Also moves the chain back to the best tour found so far.
+    pub restart_from_best: bool,
+}
+
+impl Reheat {
+    pub fn create(stagnation: usize, factor: f64, max_reheats: usize) -> Self {
+        Self {
+            stagnation,
+            factor,
+            max_reheats,
+            restart_from_best: false,
+        }
+    }
+
+    pub fn with_restart_from_best(mut self) -> Self {
+        self.restart_from_best = true;
+        self
+    }
+}
+
+/// A reheat that happened during a run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReheatEvent {
+    pub iter: usize,
+    /// Temperature right after the reheat.
+    pub temperature: f64,
+    /// Whether the chain went back to the best tour.
+    pub restarted: bool,
+}
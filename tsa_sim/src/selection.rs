@@ -0,0 +1,218 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+
+use crate::moves::Operator;
+
+/// Share of the credit an accepted move earns when it did not shorten the
+/// tour; shortening it earns the full credit.
+const ACCEPTANCE_CREDIT: f64 = 0.25;
+/// Floor on the share of an operator with a positive weight, so none of
+/// them stops being tried for good.
+const MIN_SHARE: f64 = 0.02;
+
+/// How [`crate::TSA`] picks the operator for each move.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OperatorSelection {
+    /// Always the weights given in the config.
+    #[default]
+    Fixed,
+    /// A roulette wheel that, at every temperature step, moves each
+    /// operator's share towards its share of the credit earned during the
+    /// step. `reaction` in `0.0..=1.0` is how far it moves.
+    Adaptive { reaction: f64 },
+}
+
+/// What an operator did over a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OperatorStats {
+    pub tries: usize,
+    pub acceptances: usize,
+    pub improvements: usize,
+}
+
+impl OperatorStats {
+    pub fn acceptance_rate(&self) -> f64 {
+        self.acceptances as f64 / self.tries.max(1) as f64
+    }
+
+    pub fn improvement_rate(&self) -> f64 {
+        self.improvements as f64 / self.tries.max(1) as f64
+    }
+}
+
+/// The operator wheel of a run and the statistics it keeps.
+#[derive(Debug, Clone)]
+pub struct OperatorSelector {
+    operators: Vec<Operator>,
+    shares: Vec<f64>,
+    // Operators configured with a positive weight; the others are never picked.
+    enabled: Vec<bool>,
+    wheel: WeightedIndex<f64>,
+    selection: OperatorSelection,
+    stats: Vec<OperatorStats>,
+    // Tries and credit since the last temperature step.
+    step_tries: Vec<usize>,
+    step_credit: Vec<f64>,
+}
+
+impl OperatorSelector {
+    /// Panics if no weight is positive, one is negative, or an adaptive
+    /// `reaction` is outside `0.0..=1.0`.
+    pub fn create(operators: &[(Operator, f64)], selection: OperatorSelection) -> Self {
+        if let OperatorSelection::Adaptive { reaction } = selection {
+            assert!(
+                (0.0..=1.0).contains(&reaction),
+                "Invalid reaction: {}",
+                reaction
+            );
+        }
+        let wheel = WeightedIndex::new(operators.iter().map(|(_, weight)| *weight))
+            .unwrap_or_else(|err| panic!("Invalid operator weights: {}", err));
+        let total = operators.iter().map(|(_, weight)| weight).sum::<f64>();
+        Self {
+            operators: operators.iter().map(|(operator, _)| *operator).collect(),
+            shares: operators.iter().map(|(_, weight)| weight / total).collect(),
+            enabled: operators.iter().map(|(_, weight)| *weight > 0.0).collect(),
+            wheel,
+            selection,
+            stats: vec![OperatorStats::default(); operators.len()],
+            step_tries: vec![0; operators.len()],
+            step_credit: vec![0.0; operators.len()],
+        }
+    }
+
+    /// Index of the operator for the next move.
+    pub fn pick<R: Rng>(&self, rng: &mut R) -> usize {
+        self.wheel.sample(rng)
+    }
+
+    pub fn operator(&self, index: usize) -> Operator {
+        self.operators[index]
+    }
+
+    pub fn record(&mut self, index: usize, accepted: bool, improved: bool) {
+        let stats = &mut self.stats[index];
+        stats.tries += 1;
+        self.step_tries[index] += 1;
+        if accepted {
+            stats.acceptances += 1;
+            self.step_credit[index] += ACCEPTANCE_CREDIT;
+        }
+        if improved {
+            stats.improvements += 1;
+            self.step_credit[index] += 1.0 - ACCEPTANCE_CREDIT;
+        }
+    }
+
+    /// Closes a temperature step, re-weighting the wheel when adaptive.
+    pub fn end_step(&mut self) {
+        if let OperatorSelection::Adaptive { reaction } = self.selection {
+            let scores = self
+                .step_credit
+                .iter()
+                .zip(self.step_tries.iter())
+                .map(|(credit, tries)| credit / (*tries).max(1) as f64)
+                .collect::<Vec<_>>();
+            let total = scores.iter().sum::<f64>();
+            if total > 0.0 {
+                for ((share, score), enabled) in
+                    self.shares.iter_mut().zip(scores).zip(self.enabled.iter())
+                {
+                    *share = if *enabled {
+                        ((1.0 - reaction) * *share + reaction * score / total).max(MIN_SHARE)
+                    } else {
+                        0.0
+                    };
+                }
+                let total = self.shares.iter().sum::<f64>();
+                self.shares.iter_mut().for_each(|share| *share /= total);
+                self.wheel = WeightedIndex::new(self.shares.iter()).unwrap();
+            }
+        }
+        self.step_tries.iter_mut().for_each(|tries| *tries = 0);
+        self.step_credit.iter_mut().for_each(|credit| *credit = 0.0);
+    }
+
+    /// Current probability of each operator being picked.
+    pub fn shares(&self) -> Vec<(Operator, f64)> {
+        self.operators
+            .iter()
+            .copied()
+            .zip(self.shares.iter().copied())
+            .collect()
+    }
+
+    pub fn stats(&self) -> Vec<(Operator, OperatorStats)> {
+        self.operators
+            .iter()
+            .copied()
+            .zip(self.stats.iter().copied())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        moves::Operator,
+        selection::{OperatorSelection, OperatorSelector},
+    };
+
+    #[test]
+    fn credit_moves_the_wheel() {
+        let operators = [(Operator::TwoOpt, 1.0), (Operator::KSwap, 1.0)];
+        let mut selector =
+            OperatorSelector::create(&operators, OperatorSelection::Adaptive { reaction: 0.5 });
+        for _ in 0..10 {
+            selector.record(0, true, true);
+            selector.record(1, false, false);
+            selector.end_step();
+        }
+        let shares = selector.shares();
+        assert!(shares[0].1 > 0.9);
+        assert!(shares[1].1 > 0.0);
+        assert!((shares[0].1 + shares[1].1 - 1.0).abs() < 1e-9);
+
+        let stats = selector.stats();
+        assert_eq!(stats[0].1.tries, 10);
+        assert_eq!(stats[0].1.improvements, 10);
+        assert_eq!(stats[1].1.acceptances, 0);
+    }
+
+    #[test]
+    fn zero_weight_operators_stay_off_the_wheel() {
+        let operators = [(Operator::TwoOpt, 1.0), (Operator::KSwap, 0.0)];
+        let mut selector =
+            OperatorSelector::create(&operators, OperatorSelection::Adaptive { reaction: 0.5 });
+        for _ in 0..10 {
+            selector.record(0, false, false);
+            selector.end_step();
+            selector.record(0, true, true);
+            selector.end_step();
+        }
+        assert_eq!(
+            selector.shares(),
+            vec![(Operator::TwoOpt, 1.0), (Operator::KSwap, 0.0)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid reaction")]
+    fn reaction_must_be_a_fraction() {
+        OperatorSelector::create(
+            &[(Operator::TwoOpt, 1.0)],
+            OperatorSelection::Adaptive { reaction: 1.5 },
+        );
+    }
+
+    #[test]
+    fn fixed_selection_keeps_the_config_weights() {
+        let operators = [(Operator::TwoOpt, 3.0), (Operator::OrOpt, 1.0)];
+        let mut selector = OperatorSelector::create(&operators, OperatorSelection::Fixed);
+        selector.record(1, true, true);
+        selector.end_step();
+        assert_eq!(
+            selector.shares(),
+            vec![(Operator::TwoOpt, 0.75), (Operator::OrOpt, 0.25)]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    fmt::{self, Display},
+    time::Duration,
+};
+
+/// Where a run stands, as the stop conditions see it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub iter: usize,
+    /// Time since the run was created.
+    pub elapsed: Duration,
+    pub temperature: f64,
+    pub best_distance: f64,
+    pub best_iter: usize,
+}
+
+/// When [`crate::TSA::run`] stops, on top of the schedule's own
+/// `qtd_iters`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopCondition {
+    /// After this many iterations.
+    Iterations(usize),
+    TimeLimit(Duration),
+    /// Once the best tour is this short or shorter.
+    TargetDistance(f64),
+    /// After this many iterations without a new best tour.
+    NoImprovement(usize),
+    /// Once the temperature is below this.
+    Temperature(f64),
+    /// As soon as one of them is met.
+    AnyOf(Vec<StopCondition>),
+    /// Once all of them are met at the same time; never if empty.
+    AllOf(Vec<StopCondition>),
+}
+
+impl StopCondition {
+    /// The condition met at `progress`, if any: for [`StopCondition::AnyOf`]
+    /// the first of its conditions met, otherwise the condition itself.
+    pub fn met(&self, progress: &Progress) -> Option<StopCondition> {
+        let met = match self {
+            StopCondition::Iterations(iters) => progress.iter >= *iters,
+            StopCondition::TimeLimit(limit) => progress.elapsed >= *limit,
+            StopCondition::TargetDistance(target) => progress.best_distance <= *target,
+            StopCondition::NoImprovement(iters) => {
+                progress.iter.saturating_sub(progress.best_iter) >= *iters
+            }
+            StopCondition::Temperature(threshold) => progress.temperature < *threshold,
+            StopCondition::AnyOf(conditions) => {
+                return conditions
+                    .iter()
+                    .find_map(|condition| condition.met(progress))
+            }
+            StopCondition::AllOf(conditions) => {
+                !conditions.is_empty()
+                    && conditions
+                        .iter()
+                        .all(|condition| condition.met(progress).is_some())
+            }
+        };
+        met.then(|| self.clone())
+    }
+}
+
+impl Display for StopCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |conditions: &[StopCondition]| {
+            conditions
+                .iter()
+                .map(|condition| condition.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            StopCondition::Iterations(iters) => write!(f, "{} iterations", iters),
+            StopCondition::TimeLimit(limit) => write!(f, "time limit of {:?}", limit),
+            StopCondition::TargetDistance(target) => write!(f, "target distance {}", target),
+            StopCondition::NoImprovement(iters) => {
+                write!(f, "no improvement in {} iterations", iters)
+            }
+            StopCondition::Temperature(threshold) => {
+                write!(f, "temperature below {}", threshold)
+            }
+            StopCondition::AnyOf(conditions) => write!(f, "any of ({})", list(conditions)),
+            StopCondition::AllOf(conditions) => write!(f, "all of ({})", list(conditions)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::stop::{Progress, StopCondition};
+
+    #[test]
+    fn combinators_report_what_was_met() {
+        let progress = Progress {
+            iter: 500,
+            elapsed: Duration::from_secs(2),
+            temperature: 0.5,
+            best_distance: 430.0,
+            best_iter: 100,
+        };
+        assert_eq!(StopCondition::Iterations(501).met(&progress), None);
+        assert_eq!(
+            StopCondition::TimeLimit(Duration::from_secs(3)).met(&progress),
+            None
+        );
+        assert_eq!(StopCondition::TargetDistance(426.0).met(&progress), None);
+        let stale = StopCondition::NoImprovement(400);
+        assert_eq!(stale.met(&progress), Some(stale.clone()));
+        let cold = StopCondition::Temperature(1.0);
+
+        let any = StopCondition::AnyOf(vec![StopCondition::Iterations(1000), cold.clone()]);
+        assert_eq!(any.met(&progress), Some(cold.clone()));
+        let all = StopCondition::AllOf(vec![stale.clone(), cold.clone()]);
+        assert_eq!(all.met(&progress), Some(all.clone()));
+        let not_all = StopCondition::AllOf(vec![stale, StopCondition::Iterations(1000)]);
+        assert_eq!(not_all.met(&progress), None);
+        assert_eq!(StopCondition::AllOf(vec![]).met(&progress), None);
+        assert_eq!(StopCondition::AnyOf(vec![]).met(&progress), None);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    sync::{Barrier, Mutex},
+    thread,
+};
+
+use data_retrieve::{tour::Tour, Data};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    construction::Construction, cooling_methods::ConstantCooling, moves::Operator, TSAConfig, TSA,
+};
+
+/// How [`ParallelTempering`] runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperingConfig {
+    /// One replica per temperature, from the hottest to the coldest. All
+    /// must be positive.
+    pub temperatures: Vec<f64>,
+    /// Iterations every replica runs.
+    pub qtd_iters: usize,
+    /// Iterations between two rounds of swap attempts.
+    pub swap_interval: usize,
+    pub operators: Vec<(Operator, f64)>,
+    pub construction: Construction,
+    /// Seeds the swaps; replica `k` is seeded with `seed + k`.
+    pub seed: u64,
+}
+
+impl TemperingConfig {
+    pub fn create(temperatures: Vec<f64>, qtd_iters: usize, swap_interval: usize) -> Self {
+        Self {
+            temperatures,
+            qtd_iters,
+            swap_interval,
+            operators: vec![(Operator::KSwap, 1.0)],
+            construction: Construction::Random,
+            seed: thread_rng().gen(),
+        }
+    }
+
+    /// `rungs` temperatures from `hottest` down to `coldest`, with the same
+    /// ratio between neighbours.
+    pub fn geometric_ladder(hottest: f64, coldest: f64, rungs: usize) -> Vec<f64> {
+        if rungs < 2 {
+            return vec![hottest; rungs];
+        }
+        let ratio = (coldest / hottest).powf(1.0 / (rungs - 1) as f64);
+        (0..rungs)
+            .map(|rung| hottest * ratio.powi(rung as i32))
+            .collect()
+    }
+
+    pub fn with_operators(mut self, operators: Vec<(Operator, f64)>) -> Self {
+        self.operators = operators;
+        self
+    }
+
+    pub fn with_construction(mut self, construction: Construction) -> Self {
+        self.construction = construction;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// Replica exchange: a [`TSA`] per temperature of the ladder, stepped on its
+/// own thread, with Metropolis swaps of tours between neighbouring
+/// temperatures.
+pub struct ParallelTempering {
+    replicas: Vec<TSA<ConstantCooling>>,
+    // Per rung, between temperatures `i` and `i + 1`.
+    swap_tries: Vec<usize>,
+    swap_acceptances: Vec<usize>,
+    rounds: usize,
+    rng: StdRng,
+    config: TemperingConfig,
+}
+
+impl ParallelTempering {
+    /// Panics without temperatures, or if one is not positive.
+    pub fn create(data: Data, config: TemperingConfig) -> Self {
+        Self::create_replicas(config, |replica_config| TSA::create(data, replica_config))
+    }
+
+    /// Panics without temperatures, or if one is not positive.
+    pub fn create_from_matrix(distances: Vec<Vec<f64>>, config: TemperingConfig) -> Self {
+        Self::create_replicas(config, |replica_config| {
+            TSA::create_from_matrix(distances, replica_config)
+        })
+    }
+
+    // `create` builds the first replica; the others share its matrix.
+    fn create_replicas<F>(config: TemperingConfig, create: F) -> Self
+    where
+        F: FnOnce(TSAConfig<ConstantCooling>) -> TSA<ConstantCooling>,
+    {
+        assert!(
+            !config.temperatures.is_empty()
+                && config
+                    .temperatures
+                    .iter()
+                    .all(|temperature| *temperature > 0.0),
+            "Invalid temperatures: {:?}",
+            config.temperatures
+        );
+        let replica_config = |index: usize| {
+            TSAConfig::<ConstantCooling>::create(
+                0.0,
+                config.temperatures[index],
+                config.qtd_iters,
+                config.swap_interval.max(1),
+            )
+            .with_operators(config.operators.clone())
+            .with_construction(config.construction)
+            .with_seed(config.seed.wrapping_add(index as u64))
+        };
+        let mut replicas = vec![create(replica_config(0))];
+        for index in 1..config.temperatures.len() {
+            let replica = TSA::create_shared(
+                replicas[0].data.clone(),
+                replicas[0].distances.clone(),
+                replica_config(index),
+            );
+            replicas.push(replica);
+        }
+        let rungs = replicas.len().saturating_sub(1);
+        Self {
+            replicas,
+            swap_tries: vec![0; rungs],
+            swap_acceptances: vec![0; rungs],
+            rounds: 0,
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+        }
+    }
+
+    /// Runs every replica for `qtd_iters`, attempting swaps every
+    /// `swap_interval` iterations.
+    pub fn run(&mut self) {
+        let interval = self.config.swap_interval.max(1);
+        let qtd_iters = self.config.qtd_iters;
+        let rounds = qtd_iters.div_ceil(interval);
+        // One worker per replica for the whole run. Each round the workers
+        // step their replica, wait for the others, then wait again while the
+        // swaps are attempted here.
+        let replicas = std::mem::take(&mut self.replicas)
+            .into_iter()
+            .map(Mutex::new)
+            .collect::<Vec<_>>();
+        let barrier = Barrier::new(replicas.len() + 1);
+        thread::scope(|scope| {
+            for replica in replicas.iter() {
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    for round in 0..rounds {
+                        let steps = interval.min(qtd_iters - round * interval);
+                        let mut replica = replica.lock().unwrap();
+                        for _ in 0..steps {
+                            replica.gen_next_solution();
+                        }
+                        drop(replica);
+                        barrier.wait();
+                        barrier.wait();
+                    }
+                });
+            }
+            for _ in 0..rounds {
+                barrier.wait();
+                self.attempt_swaps(&replicas);
+                barrier.wait();
+            }
+        });
+        self.replicas = replicas
+            .into_iter()
+            .map(|replica| replica.into_inner().unwrap())
+            .collect();
+    }
+
+    // Alternates between the even and the odd rungs, so every replica takes
+    // part in at most one swap a round.
+    fn attempt_swaps(&mut self, replicas: &[Mutex<TSA<ConstantCooling>>]) {
+        let first = self.rounds % 2;
+        self.rounds += 1;
+        for rung in (first..self.swap_tries.len()).step_by(2) {
+            let mut hot = replicas[rung].lock().unwrap();
+            let mut cold = replicas[rung + 1].lock().unwrap();
+            let beta_hot = 1.0 / self.config.temperatures[rung];
+            let beta_cold = 1.0 / self.config.temperatures[rung + 1];
+            let exponent =
+                (beta_cold - beta_hot) * (cold.get_current_distance() - hot.get_current_distance());
+            self.swap_tries[rung] += 1;
+            if exponent >= 0.0 || self.rng.gen_range(0.0..1.0) < exponent.exp() {
+                hot.exchange_solutions(&mut cold);
+                self.swap_acceptances[rung] += 1;
+            }
+        }
+    }
+
+    /// Share of accepted swaps at each rung, the one between temperatures
+    /// `i` and `i + 1` first.
+    pub fn get_swap_rates(&self) -> Vec<f64> {
+        self.swap_acceptances
+            .iter()
+            .zip(self.swap_tries.iter())
+            .map(|(acceptances, tries)| *acceptances as f64 / (*tries).max(1) as f64)
+            .collect()
+    }
+
+    pub fn get_replicas(&self) -> &[TSA<ConstantCooling>] {
+        &self.replicas
+    }
+
+    fn best_replica(&self) -> &TSA<ConstantCooling> {
+        self.replicas
+            .iter()
+            .min_by(|a, b| a.get_best_distance().total_cmp(&b.get_best_distance()))
+            .expect("at least one temperature")
+    }
+
+    /// Best tour found by any replica.
+    pub fn get_best_solution(&self) -> &[usize] {
+        self.best_replica().get_best_solution()
+    }
+
+    pub fn get_best_distance(&self) -> f64 {
+        self.best_replica().get_best_distance()
+    }
+
+    pub fn get_best_tour(&self, name: &str) -> Tour {
+        self.best_replica().get_best_tour(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        moves::Operator,
+        tempering::{ParallelTempering, TemperingConfig},
+    };
+
+    fn run(seed: u64) -> ParallelTempering {
+        let data = data_retrieve::load("../data/inst_51.txt").unwrap();
+        let config =
+            TemperingConfig::create(TemperingConfig::geometric_ladder(30.0, 0.5, 6), 40000, 100)
+                .with_operators(vec![(Operator::TwoOpt, 1.0), (Operator::OrOpt, 1.0)])
+                .with_seed(seed);
+        let mut tempering = ParallelTempering::create(data, config);
+        tempering.run();
+        tempering
+    }
+
+    #[test]
+    fn ladder_is_geometric() {
+        let ladder = TemperingConfig::geometric_ladder(100.0, 1.0, 3);
+        assert!((ladder[0] - 100.0).abs() < 1e-9);
+        assert!((ladder[1] - 10.0).abs() < 1e-9);
+        assert!((ladder[2] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn replicas_swap_and_share_the_best_tour() {
+        let tempering = run(8);
+        let rates = tempering.get_swap_rates();
+        assert_eq!(rates.len(), 5);
+        assert!(rates.iter().all(|rate| (0.0..=1.0).contains(rate)));
+        assert!(rates.iter().any(|rate| *rate > 0.0));
+
+        let best = tempering.get_best_distance();
+        for replica in tempering.get_replicas() {
+            assert_eq!(replica.get_current_iter(), 40000);
+            assert!(best <= replica.get_best_distance());
+            assert_eq!(
+                replica.get_current_distance(),
+                replica.get_solution_distance(&replica.solution)
+            );
+        }
+        let replica = &tempering.get_replicas()[0];
+        assert_eq!(
+            replica.get_solution_distance(tempering.get_best_solution()),
+            best
+        );
+        // eil51's optimum is 426.
+        assert!(best < 426.0 * 1.1, "{}", best);
+
+        assert_eq!(run(8).get_best_solution(), tempering.get_best_solution());
+    }
+}